@@ -0,0 +1,462 @@
+//! Build, prove and verify Merklicious trees without a conductor
+//!
+//! Input files can be JSON, msgpack (as returned by the zome functions) or a transport string made
+//! by `encode_leaf_proof`.  Output is printed as JSON unless `--output` is given, in which case it
+//! is written as msgpack (or JSON when the file name ends with `.json`).
+//!
+//! Exit codes are `0` for success, `1` when a proof is invalid and `2` for any other error.
+
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process;
+use clap::{ Parser, Subcommand, ValueEnum };
+use rand::Rng;
+use serde::{ Serialize, Deserialize, de::DeserializeOwned };
+use merklicious_sdk::{
+    bundle::BundleError,
+    merkle_hash::MerkleHashError,
+    transport::{ self, TransportError },
+    verify::VerifyError,
+    HashAlgorithm,
+    LeafDataBlock,
+    LeafInput,
+    LeafProofPayload,
+    MerkleHash,
+    PaddingMode,
+    TreeBundle,
+    TreeType,
+};
+
+
+/// Errors from running a command
+#[derive(Debug, thiserror::Error)]
+enum CliError {
+    #[error("Failed to access '{}': {1}", .0.display())]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid msgpack: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("Failed to encode msgpack: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    Transport(#[from] TransportError),
+    #[error(transparent)]
+    Hash(#[from] MerkleHashError),
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+    #[error("Invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+type CliResult<T> = Result<T, CliError>;
+
+
+#[derive(Parser)]
+#[command(name = "merklicious", version, about = "Build, prove and verify Merklicious trees without a conductor")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build a tree bundle (data blocks, salts, leaves and root) from a JSON document
+    ///
+    /// The document is either an object, whose fields become leaves in document order, or a list
+    /// of `{ "label", "value" }` objects.
+    Build {
+        /// The JSON document
+        document: PathBuf,
+        /// Hex entropy for deriving salts (defaults to 32 random bytes)
+        #[arg(long)]
+        entropy: Option<String>,
+        #[arg(long, value_enum, default_value = "standard")]
+        tree_type: TreeTypeArg,
+        #[arg(long = "hash", value_enum, default_value = "sha256")]
+        hash_algorithm: HashAlgorithmArg,
+        /// Pad the tree to this many leaves
+        #[arg(long, conflicts_with = "pad_power_of_two")]
+        pad_to: Option<u64>,
+        /// Pad the tree to the next power of two
+        #[arg(long)]
+        pad_power_of_two: bool,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Create leaf proof payloads from a tree bundle
+    Prove {
+        /// A bundle made by `build` or the `export_tree` zome function
+        bundle: PathBuf,
+        /// The labels to prove; a single label gives a payload and several give a list
+        #[arg(required = true)]
+        labels: Vec<String>,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Verify a leaf proof payload (or a list of them) against a root
+    Verify {
+        payload: PathBuf,
+        /// The expected hex root
+        #[arg(long)]
+        root: String,
+    },
+    /// Decode a msgpack or transport encoded file (eg. an entry or payload) as JSON
+    Inspect {
+        file: PathBuf,
+    },
+    /// Print the hex leaf hash of a data block
+    HashLeaf {
+        label: String,
+        /// The JSON value
+        value: String,
+        /// Hex salt
+        #[arg(long, required_unless_present = "entropy", conflicts_with = "entropy")]
+        salt: Option<String>,
+        /// Hex tree entropy; the salt is derived from it and `--index`
+        #[arg(long)]
+        entropy: Option<String>,
+        /// The leaf index used with `--entropy`
+        #[arg(long, default_value_t = 0, requires = "entropy")]
+        index: usize,
+        #[arg(long = "hash", value_enum, default_value = "sha256")]
+        hash_algorithm: HashAlgorithmArg,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TreeTypeArg {
+    Standard,
+    Sparse,
+}
+
+impl From<TreeTypeArg> for TreeType {
+    fn from(arg: TreeTypeArg) -> Self {
+        match arg {
+            TreeTypeArg::Standard => TreeType::Standard,
+            TreeTypeArg::Sparse => TreeType::Sparse,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HashAlgorithmArg {
+    Sha256,
+    Keccak256,
+}
+
+impl From<HashAlgorithmArg> for HashAlgorithm {
+    fn from(arg: HashAlgorithmArg) -> Self {
+        match arg {
+            HashAlgorithmArg::Sha256 => HashAlgorithm::Sha256,
+            HashAlgorithmArg::Keccak256 => HashAlgorithm::Keccak256,
+        }
+    }
+}
+
+/// A payload file can hold one proof or a list of them
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Payloads {
+    One(LeafProofPayload),
+    Many(Vec<LeafProofPayload>),
+}
+
+
+fn main() {
+    let cli = Cli::parse();
+
+    match run( cli.command ) {
+        Ok(true) => {},
+        Ok(false) => process::exit( 1 ),
+        Err(error) => {
+            eprintln!("error: {}", error );
+            process::exit( 2 );
+        },
+    }
+}
+
+
+/// Run a command, returning `false` when verification fails
+fn run(command: Command) -> CliResult<bool> {
+    match command {
+        Command::Build { document, entropy, tree_type, hash_algorithm, pad_to, pad_power_of_two, output } => {
+            let entropy = match entropy {
+                Some(text) => hex::decode( text )?,
+                None => {
+                    let mut rng = rand::thread_rng();
+                    (0..32).map(|_| rng.gen()).collect()
+                },
+            };
+            let padding = match (pad_to, pad_power_of_two) {
+                (Some(size), _) => Some( PaddingMode::Size( size ) ),
+                (None, true) => Some( PaddingMode::PowerOfTwo ),
+                (None, false) => None,
+            };
+            let leaves = document_leaves( read_input( &document )? )?;
+            let bundle = TreeBundle::build( leaves, entropy, tree_type.into(), hash_algorithm.into(), padding )?;
+
+            eprintln!("Built tree with {} data blocks and root {}", bundle.data_blocks.len(), hex::encode( bundle.root ) );
+            write_output( &bundle, output.as_deref() )?;
+        },
+        Command::Prove { bundle, labels, output } => {
+            let bundle : TreeBundle = read_input( &bundle )?;
+            bundle.verify()?;
+
+            let mut proofs = labels.iter()
+                .map(|label| bundle.leaf_proof( label ) )
+                .collect::<Result<Vec<LeafProofPayload>, BundleError>>()?;
+            let payloads = match proofs.len() {
+                1 => Payloads::One( proofs.remove( 0 ) ),
+                _ => Payloads::Many( proofs ),
+            };
+
+            write_output( &payloads, output.as_deref() )?;
+        },
+        Command::Verify { payload, root } => {
+            let root = MerkleHash::from_hex( root.trim_start_matches("0x") )?;
+            let proofs = match read_input( &payload )? {
+                Payloads::One(proof) => vec![ proof ],
+                Payloads::Many(proofs) => proofs,
+            };
+            let mut invalid = 0;
+
+            for proof in proofs.iter() {
+                let valid = proof.root == root && proof.verify()?;
+
+                if !valid {
+                    invalid += 1;
+                }
+
+                println!("{:<7} {} (leaf {} of {})", if valid { "valid" } else { "INVALID" }, proof.target.label, proof.index, proof.total_leaves );
+            }
+
+            if invalid > 0 {
+                eprintln!("{} of {} proofs are invalid for root {}", invalid, proofs.len(), root );
+                return Ok( false );
+            }
+        },
+        Command::Inspect { file } => {
+            let value : rmpv::Value = read_input( &file )?;
+
+            println!("{}", serde_json::to_string_pretty( &to_json( &value ) )? );
+        },
+        Command::HashLeaf { label, value, salt, entropy, index, hash_algorithm } => {
+            let leaf_input = LeafInput {
+                label,
+                value: serde_json::from_str( &value )?,
+            };
+            let block = match (salt, entropy) {
+                (Some(salt), _) => LeafDataBlock {
+                    label: leaf_input.label,
+                    value: leaf_input.value,
+                    salt: hex::decode( salt )?,
+                },
+                (None, Some(entropy)) => leaf_input.into_data_block( &hex::decode( entropy )?, index )?,
+                (None, None) => return Err( CliError::Invalid( "Either --salt or --entropy is required".to_string() ) ),
+            };
+
+            println!("{}", hex::encode( HashAlgorithm::from( hash_algorithm ).leaf_hash( &block )? ) );
+        },
+    }
+
+    Ok( true )
+}
+
+
+/// Get the leaves of a JSON document
+fn document_leaves(document: serde_json::Value) -> CliResult<Vec<LeafInput>> {
+    match document {
+        serde_json::Value::Object(fields) => fields.into_iter()
+            .map(|(label, value)| Ok( LeafInput {
+                label,
+                value: serde_json::from_value( value )?,
+            }))
+            .collect(),
+        serde_json::Value::Array(_) => Ok( serde_json::from_value( document )? ),
+        _ => Err( CliError::Invalid( "Document must be a JSON object or a list of leaves".to_string() ) ),
+    }
+}
+
+
+/// Read a JSON, transport string or msgpack file
+fn read_input<T: DeserializeOwned>(path: &Path) -> CliResult<T> {
+    let bytes = fs::read( path )
+        .map_err(|error| CliError::Io( path.to_path_buf(), error ) )?;
+
+    decode_input( &bytes )
+}
+
+
+fn decode_input<T: DeserializeOwned>(bytes: &[u8]) -> CliResult<T> {
+    // msgpack maps and arrays never start with a valid UTF-8 byte
+    match std::str::from_utf8( bytes ).map( str::trim ) {
+        Ok(text) if text.starts_with('{') || text.starts_with('[') => Ok( serde_json::from_str( text )? ),
+        Ok(text) => Ok( transport::decode( text )? ),
+        Err(_) => Ok( rmp_serde::from_slice( bytes )? ),
+    }
+}
+
+
+/// Print `value` as JSON or write it to `output`
+fn write_output<T: Serialize>(value: &T, output: Option<&Path>) -> CliResult<()> {
+    let path = match output {
+        Some(path) => path,
+        None => {
+            println!("{}", serde_json::to_string_pretty( value )? );
+            return Ok(());
+        },
+    };
+    let bytes = match path.extension().and_then(|extension| extension.to_str() ) {
+        Some("json") => serde_json::to_vec_pretty( value )?,
+        _ => rmp_serde::to_vec_named( value )?,
+    };
+
+    fs::write( path, bytes )
+        .map_err(|error| CliError::Io( path.to_path_buf(), error ) )
+}
+
+
+/// Convert a msgpack value to JSON with binary as hex and every map key as a string
+fn to_json(value: &rmpv::Value) -> serde_json::Value {
+    match value {
+        rmpv::Value::Nil => serde_json::Value::Null,
+        rmpv::Value::Boolean(boolean) => serde_json::Value::Bool( *boolean ),
+        rmpv::Value::Integer(integer) => match (integer.as_u64(), integer.as_i64()) {
+            (Some(number), _) => number.into(),
+            (None, Some(number)) => number.into(),
+            (None, None) => serde_json::Value::Null,
+        },
+        rmpv::Value::F32(number) => serde_json::json!( number ),
+        rmpv::Value::F64(number) => serde_json::json!( number ),
+        rmpv::Value::String(text) => match text.as_str() {
+            Some(text) => text.into(),
+            None => hex::encode( text.as_bytes() ).into(),
+        },
+        rmpv::Value::Binary(bytes) => hex::encode( bytes ).into(),
+        rmpv::Value::Array(items) => items.iter().map( to_json ).collect(),
+        rmpv::Value::Map(entries) => serde_json::Value::Object(
+            entries.iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        rmpv::Value::String(text) if text.is_str() => text.as_str().unwrap().to_string(),
+                        other => to_json( other ).to_string(),
+                    };
+                    (key, to_json( value ))
+                })
+                .collect()
+        ),
+        rmpv::Value::Ext(kind, bytes) => serde_json::json!({
+            "ext": kind,
+            "data": hex::encode( bytes ),
+        }),
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> TreeBundle {
+        let document = serde_json::json!({
+            "name": "Alice",
+            "age": 42,
+            "email": "alice@example.com",
+        });
+
+        TreeBundle::build(
+            document_leaves( document ).unwrap(),
+            vec![ 7; 32 ],
+            TreeType::Standard,
+            HashAlgorithm::Sha256,
+            Some( PaddingMode::PowerOfTwo ),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_build_prove_verify() {
+        let bundle = bundle();
+
+        assert_eq!( bundle.data_blocks.iter().map(|block| block.label.as_str() ).collect::<Vec<&str>>(), vec![ "name", "age", "email" ] );
+        assert_eq!( bundle.leaves.len(), 4 );
+        assert!( bundle.verify().is_ok() );
+
+        for block in bundle.data_blocks.iter() {
+            assert!( bundle.leaf_proof( &block.label ).unwrap().verify().unwrap() );
+        }
+
+        assert!( bundle.leaf_proof( "missing" ).is_err() );
+    }
+
+    #[test]
+    fn test_zome_vectors() {
+        // Salts, padding and roots that the wasm32 zome's `create_tree` produces for these inputs
+        let document = serde_json::json!({
+            "name.first": "Zoë",
+            "age": 42,
+            "balance": -1250,
+        });
+        let entropy = hex::decode( "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f" ).unwrap();
+        let bundle = TreeBundle::build( document_leaves( document ).unwrap(), entropy, TreeType::Standard, HashAlgorithm::Sha256, None ).unwrap();
+
+        assert_eq!( hex::encode( &bundle.data_blocks[0].salt ), "a86acbcbc29b8fa83c83582d56a892d0f06f0e487df5277680b0635c3927e3c5" );
+        assert_eq!( hex::encode( &bundle.data_blocks[2].salt ), "69c5e78b12f30954de4feb5ff83a28c7476727be01f35fe78a40c363f3bf5fda" );
+        assert_eq!( hex::encode( bundle.leaves[1] ), "994279194e0dcea20b3be2afd6a29138de23bda7bde988bec23c053400dfed3e" );
+        assert_eq!( hex::encode( bundle.root ), "d816c9b2f144fe6b0a8259be77da9036977d037ff98f9179f465bf4b30af4336" );
+
+        let document = serde_json::json!({
+            "name": "Alice",
+            "country": "CA",
+            "over_18": true,
+        });
+        let bundle = TreeBundle::build( document_leaves( document ).unwrap(), vec![ 0x42; 32 ], TreeType::Standard, HashAlgorithm::Sha256, Some( PaddingMode::PowerOfTwo ) ).unwrap();
+
+        assert_eq!( hex::encode( bundle.leaves[3] ), "ee7d36ed40994cba2cfd3889db0deba8363b042bab1df337611e841497d90e8b" );
+        assert_eq!( hex::encode( bundle.root ), "e724a18719f9cac8c8c302b0adeee95a0987f659af3710ecd25ffb46cda07d91" );
+
+        // `hash-leaf --entropy .. --index 2`
+        let block = LeafInput {
+            label: "balance".to_string(),
+            value: rmpv::Value::from( -1250 ),
+        }.into_data_block( &( 0..32 ).collect::<Vec<u8>>(), 2 ).unwrap();
+
+        assert_eq!( hex::encode( HashAlgorithm::Sha256.leaf_hash( &block ).unwrap() ), "487b5ef88c54fd06702e687ca5540e644bac048dd0ad99378fdd8b6a9f5a150f" );
+    }
+
+    #[test]
+    fn test_decode_input() {
+        let proof = bundle().leaf_proof( "name" ).unwrap();
+        let msgpack = rmp_serde::to_vec_named( &proof ).unwrap();
+        let json = serde_json::to_vec( &proof ).unwrap();
+        let encoded = transport::encode( &proof ).unwrap();
+
+        for bytes in [ msgpack, json, encoded.into_bytes() ].iter() {
+            let decoded : LeafProofPayload = decode_input( bytes ).unwrap();
+
+            assert_eq!( decoded.root, proof.root );
+            assert!( decoded.verify().unwrap() );
+        }
+
+        let many = rmp_serde::to_vec_named( &vec![ proof.clone(), proof ] ).unwrap();
+        assert!( matches!( decode_input( &many ).unwrap(), Payloads::Many(proofs) if proofs.len() == 2 ) );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = rmpv::Value::Map(vec![
+            ( "salt".into(), rmpv::Value::Binary( vec![ 0xab, 0xcd ] ) ),
+            ( 1.into(), rmpv::Value::Array( vec![ (-1).into(), rmpv::Value::Nil ] ) ),
+        ]);
+
+        assert_eq!( to_json( &value ), serde_json::json!({
+            "salt": "abcd",
+            "1": [ -1, null ],
+        }) );
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * C ABI for verifying Merklicious proofs outside of Holochain
+ *
+ * Every function returns a merklicious_error_t (MERKLICIOUS_OK on success) and writes its result
+ * through an out pointer.  Call merklicious_last_error() for the message of the last error on the
+ * calling thread.
+ *
+ * Hashes are 32 bytes, agent public keys are the 39 byte Holochain AgentPubKey and signatures are
+ * 64 byte Ed25519 signatures.
+ */
+#ifndef MERKLICIOUS_H
+#define MERKLICIOUS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    MERKLICIOUS_OK = 0,
+    MERKLICIOUS_NULL_POINTER = 1,
+    MERKLICIOUS_INVALID_UTF8 = 2,
+    MERKLICIOUS_TRANSPORT = 3,
+    MERKLICIOUS_DECODE = 4,
+    MERKLICIOUS_HASH = 5,
+    MERKLICIOUS_VERIFY = 6,
+    MERKLICIOUS_INVALID_AGENT_KEY = 7,
+    MERKLICIOUS_JSON = 8,
+    MERKLICIOUS_PANIC = 9,
+    MERKLICIOUS_TOO_LARGE = 10,
+} merklicious_error_t;
+
+/* Message of the last error on this thread (or NULL); valid until the next call on this thread */
+const char *merklicious_last_error(void);
+
+/* Free a string returned by this library */
+void merklicious_string_free(char *text);
+
+/* Decode a leaf proof payload (transport string or msgpack) into JSON; free with
+ * merklicious_string_free */
+merklicious_error_t merklicious_decode_payload(const uint8_t *payload, size_t payload_len,
+                                               char **json_out);
+
+/* Verify a leaf proof payload (transport string or msgpack) */
+merklicious_error_t merklicious_verify_payload(const uint8_t *payload, size_t payload_len,
+                                               bool *valid);
+
+/* Get the leaf hash of a data block; value is the msgpack encoding of the leaf value */
+merklicious_error_t merklicious_hash_leaf(const char *label,
+                                          const uint8_t *value, size_t value_len,
+                                          const uint8_t *salt, size_t salt_len,
+                                          uint8_t hash_out[32]);
+
+/* Check that proof (proof_count concatenated 32 byte hashes) leads from leaf at index to root */
+merklicious_error_t merklicious_verify_proof(const uint8_t root[32],
+                                             uint64_t index,
+                                             const uint8_t leaf[32],
+                                             const uint8_t *proof, size_t proof_count,
+                                             uint64_t total_leaves,
+                                             bool *valid);
+
+/* Check an agent's Ed25519 signature of raw bytes (eg. an SD-JWT signing input) */
+merklicious_error_t merklicious_verify_signature(const uint8_t agent[39],
+                                                 const uint8_t *message, size_t message_len,
+                                                 const uint8_t signature[64],
+                                                 bool *valid);
+
+/* Check the issuer's signature of an attestation ("merklicious:attestation:v1" || holder || root) */
+merklicious_error_t merklicious_verify_attestation(const uint8_t issuer[39],
+                                                   const uint8_t holder[39],
+                                                   const uint8_t root[32],
+                                                   const uint8_t signature[64],
+                                                   bool *valid);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MERKLICIOUS_H */
@@ -0,0 +1,469 @@
+//! C ABI for verifying Merklicious proofs outside of Holochain
+//!
+//! Every function returns a [`MerkliciousError`] code (`MERKLICIOUS_OK` on success) and writes its
+//! result through an out pointer.  The message for the last error on the calling thread is
+//! available from [`merklicious_last_error`].  See `include/merklicious.h` for the C declarations.
+//!
+//! Hashes are 32 bytes, agent public keys are the 39 byte Holochain `AgentPubKey` and signatures
+//! are 64 byte Ed25519 signatures.
+
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::ffi::{ CStr, CString };
+use std::os::raw::c_char;
+use std::panic::{ self, AssertUnwindSafe };
+use ed25519_dalek::{ Signature, VerifyingKey };
+use merklicious_sdk::{
+    transport::{ self, TransportError },
+    verify::{ self, LeafProof, VerifyError },
+    merkle_hash::MerkleHashError,
+};
+
+
+/// The 3 byte prefix of a Holochain `AgentPubKey`
+pub const AGENT_PUB_KEY_PREFIX: [u8; 3] = [ 0x84, 0x20, 0x24 ];
+
+
+//
+// Errors
+//
+/// Errors from the C ABI functions
+#[derive(Debug, thiserror::Error)]
+pub enum FfiError {
+    #[error("Argument '{0}' is null")]
+    NullPointer(&'static str),
+    #[error("Argument '{0}' is not valid UTF-8")]
+    InvalidUtf8(&'static str),
+    #[error("Argument '{0}' is too large")]
+    TooLarge(&'static str),
+    #[error("{0}")]
+    Transport(#[from] TransportError),
+    #[error("Failed to decode payload: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("{0}")]
+    Hash(#[from] MerkleHashError),
+    #[error("{0}")]
+    Verify(#[from] VerifyError),
+    #[error("Invalid agent public key")]
+    InvalidAgentKey,
+    #[error("Failed to encode JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unexpected panic: {0}")]
+    Panic(String),
+}
+
+/// The C error code for each [`FfiError`] variant
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MerkliciousError {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    Transport = 3,
+    Decode = 4,
+    Hash = 5,
+    Verify = 6,
+    InvalidAgentKey = 7,
+    Json = 8,
+    Panic = 9,
+    TooLarge = 10,
+}
+
+impl FfiError {
+    /// Get the C error code for this error
+    pub fn code(&self) -> MerkliciousError {
+        match self {
+            FfiError::NullPointer(_) => MerkliciousError::NullPointer,
+            FfiError::InvalidUtf8(_) => MerkliciousError::InvalidUtf8,
+            FfiError::Transport(_) => MerkliciousError::Transport,
+            FfiError::Decode(_) => MerkliciousError::Decode,
+            FfiError::Hash(_) => MerkliciousError::Hash,
+            FfiError::Verify(_) => MerkliciousError::Verify,
+            FfiError::InvalidAgentKey => MerkliciousError::InvalidAgentKey,
+            FfiError::Json(_) => MerkliciousError::Json,
+            FfiError::Panic(_) => MerkliciousError::Panic,
+            FfiError::TooLarge(_) => MerkliciousError::TooLarge,
+        }
+    }
+}
+
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new( None ) };
+}
+
+fn set_last_error(message: Option<String>) {
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = message
+            .map(|message| CString::new( message.replace( '\0', "" ) ).unwrap() );
+    });
+}
+
+/// Run `call`, recording its error (or panic) for [`merklicious_last_error`]
+fn run<F>(call: F) -> MerkliciousError
+where
+    F: FnOnce() -> Result<(), FfiError>,
+{
+    let result = panic::catch_unwind( AssertUnwindSafe( call ) )
+        .unwrap_or_else(|panic| {
+            let message = panic.downcast_ref::<&str>().map(|text| text.to_string() )
+                .or_else(|| panic.downcast_ref::<String>().cloned() )
+                .unwrap_or_default();
+
+            Err(FfiError::Panic( message ))
+        });
+
+    match result {
+        Ok(()) => {
+            set_last_error( None );
+            MerkliciousError::Ok
+        },
+        Err(error) => {
+            set_last_error( Some( error.to_string() ) );
+            error.code()
+        },
+    }
+}
+
+
+//
+// Pointer Helpers
+//
+unsafe fn slice_arg<'a>(ptr: *const u8, len: usize, name: &'static str) -> Result<&'a [u8], FfiError> {
+    if len == 0 {
+        return Ok( &[] );
+    }
+    if ptr.is_null() {
+        return Err(FfiError::NullPointer( name ));
+    }
+
+    Ok( std::slice::from_raw_parts( ptr, len ) )
+}
+
+unsafe fn array_arg<'a, const N: usize>(ptr: *const u8, name: &'static str) -> Result<&'a [u8; N], FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::NullPointer( name ));
+    }
+
+    Ok( &*( ptr as *const [u8; N] ) )
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &'static str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::NullPointer( name ));
+    }
+
+    CStr::from_ptr( ptr ).to_str()
+        .map_err(|_| FfiError::InvalidUtf8( name ))
+}
+
+unsafe fn write_out<T>(ptr: *mut T, value: T, name: &'static str) -> Result<(), FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::NullPointer( name ));
+    }
+
+    ptr.write( value );
+    Ok(())
+}
+
+fn verifying_key(agent: &[u8; 39]) -> Result<VerifyingKey, FfiError> {
+    if agent[..3] != AGENT_PUB_KEY_PREFIX {
+        return Err(FfiError::InvalidAgentKey);
+    }
+
+    VerifyingKey::from_bytes( agent[3..35].try_into().unwrap() )
+        .map_err(|_| FfiError::InvalidAgentKey)
+}
+
+
+/// Decode a leaf proof from a transport string (UTF-8) or msgpack (eg. a saved `get_leaf_proof`
+/// result)
+pub fn decode_payload(bytes: &[u8]) -> Result<LeafProof, FfiError> {
+    Ok(
+        match std::str::from_utf8( bytes ) {
+            Ok(text) => transport::decode( text.trim() )?,
+            Err(_) => rmp_serde::from_slice( bytes )?,
+        }
+    )
+}
+
+
+//
+// Exports
+//
+/// Get the message of the last error on this thread (or null)
+///
+/// The string is owned by the library and is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn merklicious_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow().as_ref()
+            .map_or( std::ptr::null(), |message| message.as_ptr() )
+    })
+}
+
+
+/// Free a string returned by this library
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this library that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop( CString::from_raw( text ) );
+    }
+}
+
+
+/// Decode a leaf proof payload into JSON (eg. to display the revealed label and value)
+///
+/// The JSON string must be freed with [`merklicious_string_free`].
+///
+/// # Safety
+///
+/// `payload` must point to `payload_len` readable bytes and `json_out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_decode_payload(
+    payload: *const u8, payload_len: usize, json_out: *mut *mut c_char,
+) -> MerkliciousError {
+    run(|| {
+        let proof = decode_payload( slice_arg( payload, payload_len, "payload" )? )?;
+        let json = CString::new( serde_json::to_string( &proof )? ).unwrap();
+
+        write_out( json_out, json.into_raw(), "json_out" )
+    })
+}
+
+
+/// Verify a leaf proof payload (transport string or msgpack)
+///
+/// # Safety
+///
+/// `payload` must point to `payload_len` readable bytes and `valid` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_verify_payload(
+    payload: *const u8, payload_len: usize, valid: *mut bool,
+) -> MerkliciousError {
+    run(|| {
+        let proof = decode_payload( slice_arg( payload, payload_len, "payload" )? )?;
+
+        write_out( valid, proof.verify()?, "valid" )
+    })
+}
+
+
+/// Get the leaf hash of a data block
+///
+/// `value` is the msgpack encoding of the leaf value.
+///
+/// # Safety
+///
+/// `label` must be a NUL terminated string, `value` and `salt` must point to `value_len` and
+/// `salt_len` readable bytes and `hash_out` must point to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_hash_leaf(
+    label: *const c_char,
+    value: *const u8, value_len: usize,
+    salt: *const u8, salt_len: usize,
+    hash_out: *mut [u8; 32],
+) -> MerkliciousError {
+    run(|| {
+        let label = str_arg( label, "label" )?;
+        let value : rmpv::Value = rmp_serde::from_slice( slice_arg( value, value_len, "value" )? )?;
+        let hash = verify::leaf_hash( label, &value, slice_arg( salt, salt_len, "salt" )? )?;
+
+        write_out( hash_out, hash, "hash_out" )
+    })
+}
+
+
+/// Check that `proof` (`proof_count` concatenated 32 byte hashes) leads from `leaf` at `index` to
+/// `root`
+///
+/// # Safety
+///
+/// `root` and `leaf` must point to 32 readable bytes, `proof` must point to `proof_count * 32`
+/// readable bytes and `valid` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_verify_proof(
+    root: *const u8,
+    index: u64,
+    leaf: *const u8,
+    proof: *const u8, proof_count: usize,
+    total_leaves: u64,
+    valid: *mut bool,
+) -> MerkliciousError {
+    run(|| {
+        let proof_len = proof_count.checked_mul( 32 )
+            .ok_or( FfiError::TooLarge( "proof_count" ) )?;
+        let proof : Vec<[u8; 32]> = slice_arg( proof, proof_len, "proof" )?
+            .chunks_exact( 32 )
+            .map(|hash| hash.try_into().unwrap() )
+            .collect();
+
+        write_out(
+            valid,
+            verify::verify_proof( array_arg( root, "root" )?, index, array_arg( leaf, "leaf" )?, &proof, total_leaves ),
+            "valid",
+        )
+    })
+}
+
+
+/// Check an agent's Ed25519 signature of raw bytes (eg. an SD-JWT signing input)
+///
+/// # Safety
+///
+/// `agent` must point to 39 readable bytes, `message` to `message_len` readable bytes,
+/// `signature` to 64 readable bytes and `valid` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_verify_signature(
+    agent: *const u8,
+    message: *const u8, message_len: usize,
+    signature: *const u8,
+    valid: *mut bool,
+) -> MerkliciousError {
+    run(|| {
+        let key = verifying_key( array_arg( agent, "agent" )? )?;
+        let signature = Signature::from_bytes( array_arg( signature, "signature" )? );
+
+        write_out( valid, key.verify_strict( slice_arg( message, message_len, "message" )?, &signature ).is_ok(), "valid" )
+    })
+}
+
+
+/// Check the issuer's signature of an attestation (`AttestationEntry::verify`)
+///
+/// # Safety
+///
+/// `issuer` and `holder` must point to 39 readable bytes, `root` to 32 readable bytes,
+/// `signature` to 64 readable bytes and `valid` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn merklicious_verify_attestation(
+    issuer: *const u8,
+    holder: *const u8,
+    root: *const u8,
+    signature: *const u8,
+    valid: *mut bool,
+) -> MerkliciousError {
+    run(|| {
+        let key = verifying_key( array_arg( issuer, "issuer" )? )?;
+        let holder : &[u8; 39] = array_arg( holder, "holder" )?;
+        let message = verify::attestation_message( holder, array_arg( root, "root" )? );
+        let signature = Signature::from_bytes( array_arg( signature, "signature" )? );
+
+        write_out( valid, key.verify_strict( &message, &signature ).is_ok(), "valid" )
+    })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{ Signer, SigningKey };
+    use merklicious_sdk::{ HashAlgorithm, MerkleHash, verify::LeafData };
+
+    fn agent_key(signing_key: &SigningKey) -> [u8; 39] {
+        let mut agent = [ 0; 39 ];
+
+        agent[..3].copy_from_slice( &AGENT_PUB_KEY_PREFIX );
+        agent[3..35].copy_from_slice( signing_key.verifying_key().as_bytes() );
+        agent
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors : serde_json::Value = serde_json::from_str( include_str!("../../tests/vectors/merklicious_v1.json") ).unwrap();
+
+        for case in vectors["cases"].as_array().unwrap() {
+            let root = hex::decode( case["root"].as_str().unwrap() ).unwrap();
+            let leaves = case["leaves"].as_array().unwrap();
+            let total_leaves = leaves.len() + case["padding_leaves"].as_array().map_or( 0, Vec::len );
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let label = CString::new( leaf["label"].as_str().unwrap() ).unwrap();
+                let value = rmp_serde::to_vec( &serde_json::from_value::<rmpv::Value>( leaf["value"].clone() ).unwrap() ).unwrap();
+                let salt = hex::decode( leaf["salt"].as_str().unwrap() ).unwrap();
+                let proof : Vec<u8> = case["proofs"][ index ].as_array().unwrap().iter()
+                    .flat_map(|hash| hex::decode( hash.as_str().unwrap() ).unwrap() )
+                    .collect();
+                let mut hash = [ 0; 32 ];
+                let mut valid = false;
+
+                unsafe {
+                    assert_eq!( merklicious_hash_leaf( label.as_ptr(), value.as_ptr(), value.len(), salt.as_ptr(), salt.len(), &mut hash ), MerkliciousError::Ok );
+                    assert_eq!( hex::encode( hash ), leaf["leaf"] );
+
+                    assert_eq!( merklicious_verify_proof( root.as_ptr(), index as u64, hash.as_ptr(), proof.as_ptr(), proof.len() / 32, total_leaves as u64, &mut valid ), MerkliciousError::Ok );
+                    assert!( valid );
+                }
+
+                // The same proof as a transport encoded payload
+                let payload = transport::encode( &LeafProof {
+                    proof: proof.chunks_exact( 32 ).map(|hash| MerkleHash::from_slice( hash ).unwrap() ).collect(),
+                    index: index as u64,
+                    target: LeafData {
+                        label: leaf["label"].as_str().unwrap().to_string(),
+                        value: rmp_serde::from_slice( &value ).unwrap(),
+                        salt,
+                    },
+                    leaf: hash.into(),
+                    root: MerkleHash::from_slice( &root ).unwrap(),
+                    total_leaves: total_leaves as u64,
+                    hash_algorithm: HashAlgorithm::Sha256,
+                }).unwrap();
+                let mut valid = false;
+
+                unsafe {
+                    assert_eq!( merklicious_verify_payload( payload.as_ptr(), payload.len(), &mut valid ), MerkliciousError::Ok );
+                }
+                assert!( valid );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_attestation() {
+        let issuer = SigningKey::from_bytes( &[ 1; 32 ] );
+        let holder = agent_key( &SigningKey::from_bytes( &[ 2; 32 ] ) );
+        let root = [ 3; 32 ];
+        let signature = issuer.sign( &verify::attestation_message( &holder, &root ) ).to_bytes();
+        let mut valid = false;
+
+        unsafe {
+            assert_eq!( merklicious_verify_attestation( agent_key( &issuer ).as_ptr(), holder.as_ptr(), root.as_ptr(), signature.as_ptr(), &mut valid ), MerkliciousError::Ok );
+            assert!( valid );
+
+            assert_eq!( merklicious_verify_attestation( agent_key( &issuer ).as_ptr(), holder.as_ptr(), [ 4; 32 ].as_ptr(), signature.as_ptr(), &mut valid ), MerkliciousError::Ok );
+            assert!( !valid );
+
+            assert_eq!( merklicious_verify_signature( holder.as_ptr(), b"data".as_ptr(), 4, signature.as_ptr(), &mut valid ), MerkliciousError::Ok );
+            assert!( !valid );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut valid = false;
+
+        unsafe {
+            assert_eq!( merklicious_verify_payload( std::ptr::null(), 4, &mut valid ), MerkliciousError::NullPointer );
+            assert_eq!( CStr::from_ptr( merklicious_last_error() ).to_str().unwrap(), "Argument 'payload' is null" );
+
+            assert_eq!( merklicious_verify_payload( b"znope".as_ptr(), 5, &mut valid ), MerkliciousError::Transport );
+            assert_eq!( merklicious_verify_payload( [ 0x86, 0xff ].as_ptr(), 2, &mut valid ), MerkliciousError::Decode );
+            assert_eq!( merklicious_verify_attestation( [ 0; 39 ].as_ptr(), [ 0; 39 ].as_ptr(), [ 0; 32 ].as_ptr(), [ 0; 64 ].as_ptr(), &mut valid ), MerkliciousError::InvalidAgentKey );
+            assert_eq!( merklicious_verify_proof( [ 0; 32 ].as_ptr(), 0, [ 0; 32 ].as_ptr(), [ 0; 32 ].as_ptr(), usize::MAX, 1, &mut valid ), MerkliciousError::TooLarge );
+            assert_eq!( CStr::from_ptr( merklicious_last_error() ).to_str().unwrap(), "Argument 'proof_count' is too large" );
+
+            let mut json = std::ptr::null_mut();
+            let payload = b"not a payload";
+            assert_ne!( merklicious_decode_payload( payload.as_ptr(), payload.len(), &mut json ), MerkliciousError::Ok );
+            assert!( json.is_null() );
+        }
+
+        assert!( !merklicious_last_error().is_null() );
+    }
+}
@@ -0,0 +1,371 @@
+//! Data blocks, leaf proofs and tree bundles without the HDK
+//!
+//! These are re-exported from the crate root, so a [`TreeBundle`] can be built, proven and
+//! verified by native tools (eg. the CLI) built without the `hdk` feature and still match what the
+//! zome creates.
+
+use std::collections::BTreeMap;
+use rs_merkle::{ MerkleTree, algorithms };
+use serde::{ Serialize, Deserialize };
+use crate::{
+    ethereum,
+    sparse_merkle_tree,
+    verify::{ self, VerifyError },
+    HashAlgorithm,
+    MerkleHash,
+    SparseMerkleTree,
+};
+
+
+/// The current version of [`TreeBundle`]
+pub const TREE_BUNDLE_VERSION: u32 = 1;
+
+
+/// Errors from building, checking or proving a tree bundle
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum BundleError {
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+    #[error("Unsupported tree bundle version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Data block {0} ('{1}') salt does not match the bundle entropy")]
+    SaltMismatch(usize, String),
+    #[error("Bundle leaves do not match its data blocks")]
+    LeavesMismatch,
+    #[error("Bundle root does not match its leaves")]
+    RootMismatch,
+    #[error("Couldn't get the Merkle root")]
+    NoRoot,
+    #[error("Padding is only supported for standard {:?} trees", HashAlgorithm::Sha256)]
+    UnsupportedPadding,
+    #[error("Padding size ({0}) is smaller than the number of leaves ({1})")]
+    PaddingTooSmall(u64, u64),
+    #[error("Sparse trees only support {:?} hashing", HashAlgorithm::Sha256)]
+    UnsupportedSparseHash,
+    #[error("Leaf proofs need a standard tree; not a {0:?} tree")]
+    NotStandardTree(TreeType),
+    #[error("Bundle has no data block with the label '{0}'")]
+    MissingLabel(String),
+    #[error("Couldn't create proof for leaf {0}")]
+    NoProof(usize),
+}
+
+
+
+//
+// Leaves
+//
+/// The piece of data that a Merkle Tree leaf represents
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeafDataBlock {
+    /// The field descriptor
+    pub label: String,
+    /// The field data
+    pub value: rmpv::Value,
+    /// Some entropy to prevent value guessing
+    #[serde(with = "serde_bytes")]
+    pub salt: Vec<u8>,
+}
+
+impl LeafDataBlock {
+    /// Get a sha256 hash of this struct
+    pub fn hash(&self) -> Result<[u8; 32], VerifyError> {
+        verify::leaf_hash( &self.label, &self.value, &self.salt )
+    }
+}
+
+/// Input required for a leaf data block
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeafInput {
+    /// The field descriptor
+    pub label: String,
+    /// The field data
+    pub value: rmpv::Value,
+}
+
+impl LeafInput {
+    /// Create a [`LeafDataBlock`] from this leaf input
+    ///
+    /// This method generates a deterministic salt using the entropy and index provided
+    pub fn into_data_block(self, entropy: &[u8], index: usize) -> Result<LeafDataBlock, VerifyError> {
+        Ok(
+            LeafDataBlock {
+                label: self.label,
+                value: self.value,
+                salt: verify::derive_salt( entropy, index )?,
+            }
+        )
+    }
+}
+
+/// All the information required to verify a leaf
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeafProofPayload {
+    /// The Merkle proof hash list
+    pub proof: Vec<MerkleHash>,
+    /// The leaf's index in the Merkle tree
+    pub index: u64,
+    /// The revealed leaf data
+    pub target: LeafDataBlock,
+    /// The hash of the target leaf
+    pub leaf: MerkleHash,
+    /// The Merkle tree's root hash
+    pub root: MerkleHash,
+    /// The total number of leaves in the Merkle tree
+    pub total_leaves: u64,
+    /// The hash function of the tree (left out when it is the default SHA-256)
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_sha256")]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl LeafProofPayload {
+    /// Verify that `target` is the leaf at `index` of the tree with `root`
+    pub fn verify(&self) -> Result<bool, VerifyError> {
+        Ok(
+            self.hash_algorithm.leaf_hash( &self.target )? == self.leaf
+                && self.hash_algorithm.verify_proof( &self.root, self.index, &self.leaf, &MerkleHash::to_raw_vec( &self.proof ), self.total_leaves )
+        )
+    }
+}
+
+impl HashAlgorithm {
+    /// Get the leaf hash of a data block
+    pub fn leaf_hash(&self, block: &LeafDataBlock) -> Result<[u8; 32], VerifyError> {
+        self.hash_leaf( &block.label, &block.value, &block.salt )
+    }
+
+    /// Get the proof for the leaf at `index` of a standard tree built from `leaves`
+    pub fn proof(&self, leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= leaves.len() {
+            return None;
+        }
+
+        match self {
+            HashAlgorithm::Sha256 => Some(
+                MerkleTree::<algorithms::Sha256>::from_leaves( leaves )
+                    .proof( &[ index ] )
+                    .proof_hashes()
+                    .to_vec()
+            ),
+            HashAlgorithm::Keccak256 => ethereum::StandardMerkleTree::from_leaves( leaves )?
+                .proof( index ),
+        }
+    }
+}
+
+
+
+//
+// Trees
+//
+/// The kinds of Merkle tree that can be created
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TreeType {
+    /// A dense tree where leaves are positioned by their index ([`rs_merkle::MerkleTree`])
+    #[default]
+    Standard,
+    /// A fixed depth tree where leaves are positioned by the hash of their label
+    /// ([`SparseMerkleTree`])
+    Sparse,
+}
+
+/// How to pad a tree's leaves so that `total_leaves` does not reveal the number of data blocks
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PaddingMode {
+    /// Pad to the next power of two
+    PowerOfTwo,
+    /// Pad to an exact number of leaves
+    Size(u64),
+}
+
+impl PaddingMode {
+    /// Get the total number of leaves after padding `leaf_count` leaves
+    pub fn padded_size(&self, leaf_count: u64) -> Result<u64, BundleError> {
+        match self {
+            PaddingMode::PowerOfTwo => Ok( leaf_count.next_power_of_two() ),
+            PaddingMode::Size(size) => match *size >= leaf_count {
+                true => Ok( *size ),
+                false => Err( BundleError::PaddingTooSmall( *size, leaf_count ) ),
+            },
+        }
+    }
+}
+
+
+
+//
+// Tree Bundle
+//
+/// A self-contained export of a tree for moving it between devices or cells
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreeBundle {
+    /// The bundle format version (see [`TREE_BUNDLE_VERSION`])
+    pub version: u32,
+    /// The hash function used by the tree
+    pub hash_algorithm: HashAlgorithm,
+    /// The kind of Merkle tree
+    pub tree_type: TreeType,
+    /// The tree's secret entropy used for deriving salts and padding leaves
+    #[serde(with = "serde_bytes")]
+    pub entropy: Vec<u8>,
+    /// Every data block in leaf order
+    pub data_blocks: Vec<LeafDataBlock>,
+    /// Every leaf hash, including padding
+    pub leaves: Vec<[u8; 32]>,
+    /// The root hash of the tree
+    pub root: [u8; 32],
+    /// Whether the tree stores its intermediate layers
+    pub store_layers: bool,
+    /// The chunk size the tree was created with
+    pub chunk_size: Option<u64>,
+    /// The tree entry's metadata
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+
+impl TreeBundle {
+    /// Check that the salts, leaves and root can all be recomputed from the bundle
+    pub fn verify(&self) -> Result<(), BundleError> {
+        if self.version != TREE_BUNDLE_VERSION {
+            return Err( BundleError::UnsupportedVersion( self.version ) );
+        }
+
+        let mut leaves = Vec::with_capacity( self.leaves.len() );
+
+        for (index, block) in self.data_blocks.iter().enumerate() {
+            if verify::derive_salt( &self.entropy, index )? != block.salt {
+                return Err( BundleError::SaltMismatch( index, block.label.clone() ) );
+            }
+
+            leaves.push( self.hash_algorithm.leaf_hash( block )? );
+        }
+
+        for index in leaves.len()..self.leaves.len() {
+            leaves.push( verify::padding_leaf( &self.entropy, index )? );
+        }
+
+        if leaves != self.leaves {
+            return Err( BundleError::LeavesMismatch );
+        }
+
+        if self.calculate_root()? != self.root {
+            return Err( BundleError::RootMismatch );
+        }
+
+        Ok(())
+    }
+
+    /// Build the bundle for a new tree without a conductor
+    ///
+    /// Salts and padding leaves are derived from `entropy` the same way as the `create_tree` zome
+    /// function so the bundle can be imported to get an identical tree.
+    pub fn build(
+        leaves: Vec<LeafInput>,
+        entropy: Vec<u8>,
+        tree_type: TreeType,
+        hash_algorithm: HashAlgorithm,
+        padding: Option<PaddingMode>,
+    ) -> Result<Self, BundleError> {
+        let data_blocks = leaves.into_iter()
+            .enumerate()
+            .map(|(index, leaf_input)| leaf_input.into_data_block( &entropy, index ) )
+            .collect::<Result<Vec<LeafDataBlock>, VerifyError>>()?;
+        let mut leaves = data_blocks.iter()
+            .map(|block| hash_algorithm.leaf_hash( block ) )
+            .collect::<Result<Vec<[u8; 32]>, VerifyError>>()?;
+
+        if let Some(padding) = padding {
+            if tree_type != TreeType::Standard || !hash_algorithm.is_sha256() {
+                return Err( BundleError::UnsupportedPadding );
+            }
+
+            let padded_size = padding.padded_size( leaves.len() as u64 )? as usize;
+            for index in leaves.len()..padded_size {
+                leaves.push( verify::padding_leaf( &entropy, index )? );
+            }
+        }
+
+        let mut bundle = TreeBundle {
+            version: TREE_BUNDLE_VERSION,
+            hash_algorithm,
+            tree_type,
+            entropy,
+            data_blocks,
+            leaves,
+            root: [0; 32],
+            store_layers: false,
+            chunk_size: None,
+            metadata: BTreeMap::new(),
+        };
+        bundle.root = bundle.calculate_root()?;
+
+        Ok( bundle )
+    }
+
+    /// Calculate the root from `leaves` (and `data_blocks` for sparse trees)
+    pub fn calculate_root(&self) -> Result<[u8; 32], BundleError> {
+        match self.tree_type {
+            TreeType::Standard => self.hash_algorithm.root( &self.leaves )
+                .ok_or( BundleError::NoRoot ),
+            TreeType::Sparse if self.hash_algorithm != HashAlgorithm::Sha256 => {
+                Err( BundleError::UnsupportedSparseHash )
+            },
+            TreeType::Sparse => {
+                let mut tree = SparseMerkleTree::new();
+
+                for (block, leaf) in self.data_blocks.iter().zip( self.leaves.iter() ) {
+                    tree.insert( sparse_merkle_tree::label_key( &block.label ), *leaf );
+                }
+
+                Ok( tree.root() )
+            },
+        }
+    }
+
+    /// Get the proof for the data block with `label` (standard trees only)
+    pub fn leaf_proof(&self, label: &str) -> Result<LeafProofPayload, BundleError> {
+        if self.tree_type != TreeType::Standard {
+            return Err( BundleError::NotStandardTree( self.tree_type.clone() ) );
+        }
+
+        let index = self.data_blocks.iter()
+            .position(|block| block.label == label )
+            .ok_or( BundleError::MissingLabel( label.to_string() ) )?;
+        let proof = self.hash_algorithm.proof( &self.leaves, index )
+            .ok_or( BundleError::NoProof( index ) )?;
+
+        Ok(
+            LeafProofPayload {
+                proof: MerkleHash::from_raw_vec( proof ),
+                index: index as u64,
+                target: self.data_blocks[ index ].clone(),
+                leaf: self.leaves[ index ].into(),
+                root: self.root.into(),
+                total_leaves: self.leaves.len() as u64,
+                hash_algorithm: self.hash_algorithm.clone(),
+            }
+        )
+    }
+
+    /// Get the padding that recreates `leaves` from `data_blocks`
+    pub fn padding(&self) -> Option<PaddingMode> {
+        match self.leaves.len() > self.data_blocks.len() {
+            true => Some( PaddingMode::Size( self.leaves.len() as u64 ) ),
+            false => None,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_size() {
+        assert_eq!( PaddingMode::PowerOfTwo.padded_size( 10 ).unwrap(), 16 );
+        assert_eq!( PaddingMode::PowerOfTwo.padded_size( 16 ).unwrap(), 16 );
+        assert_eq!( PaddingMode::Size( 64 ).padded_size( 10 ).unwrap(), 64 );
+        assert_eq!( PaddingMode::Size( 8 ).padded_size( 10 ), Err( BundleError::PaddingTooSmall( 8, 10 ) ) );
+    }
+}
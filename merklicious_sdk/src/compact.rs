@@ -0,0 +1,354 @@
+//! Compact binary encoding of a leaf proof for QR codes
+//!
+//! The encoding is a 2 byte header (`[ version, flags ]`) followed by the body, which is deflated
+//! when that makes it smaller.  The body is
+//!
+//! - varint `index` and `total_leaves`
+//! - the root (32 bytes, or a length byte and prefix when truncated)
+//! - varint proof hash count followed by each 32 byte hash
+//! - varint length prefixed label, salt and msgpack encoded value
+//!
+//! The leaf hash is not included because it can be recomputed from the revealed data block.
+//! Truncating the root saves space but is **insecure**; a verifier only learns that the proof
+//! leads to a root starting with the given prefix, which is much easier to forge.
+
+use std::convert::TryFrom;
+
+
+/// The version byte at the start of every compact proof
+pub const COMPACT_VERSION: u8 = 1;
+
+/// Flag set when the root has been truncated
+pub const FLAG_TRUNCATED_ROOT: u8 = 0b0000_0001;
+
+/// Flag set when the body is deflated
+pub const FLAG_DEFLATED: u8 = 0b0000_0010;
+
+/// The shortest root prefix that can be encoded
+pub const MIN_ROOT_PREFIX: usize = 8;
+
+/// The most bytes a deflated body may inflate to
+pub const MAX_BODY_SIZE: usize = 64 * 1024;
+
+const HEADER_SIZE: usize = 2;
+
+
+/// Errors from encoding or decoding a compact proof
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum CompactError {
+    #[error("Unsupported compact proof version {0}; expected {}", COMPACT_VERSION)]
+    UnsupportedVersion(u8),
+    #[error("Unknown compact proof flags {0:#010b}")]
+    UnknownFlags(u8),
+    #[error("Compact proof ended unexpectedly")]
+    UnexpectedEnd,
+    #[error("Compact proof has {0} unexpected trailing bytes")]
+    TrailingBytes(usize),
+    #[error("Invalid varint")]
+    InvalidVarint,
+    #[error("Root prefix must be {} to 32 bytes; found {0}", MIN_ROOT_PREFIX)]
+    InvalidRootPrefix(usize),
+    #[error("Label is not valid UTF-8")]
+    InvalidLabel,
+    #[error("Failed to inflate compact proof: {0}")]
+    Inflate(String),
+    #[error("Compact proof is {size} bytes which is over the budget of {budget} bytes")]
+    OverBudget { size: usize, budget: usize },
+}
+
+
+/// The fields of a leaf proof that are carried in the compact encoding
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactProof {
+    pub index: u64,
+    pub total_leaves: u64,
+    /// The full root or, when truncated, a prefix of it
+    pub root: Vec<u8>,
+    pub proof: Vec<[u8; 32]>,
+    pub label: String,
+    pub salt: Vec<u8>,
+    /// The msgpack encoding of the leaf value
+    pub value: Vec<u8>,
+}
+
+impl CompactProof {
+    /// Is the root a truncated prefix
+    pub fn is_truncated(&self) -> bool {
+        self.root.len() < 32
+    }
+
+    /// Encode this proof, failing if the result is larger than `budget`
+    pub fn to_bytes(&self, budget: Option<usize>) -> Result<Vec<u8>, CompactError> {
+        let mut flags = 0;
+        let mut body = Vec::new();
+
+        write_varint( &mut body, self.index );
+        write_varint( &mut body, self.total_leaves );
+
+        if self.is_truncated() {
+            if self.root.len() < MIN_ROOT_PREFIX {
+                return Err( CompactError::InvalidRootPrefix( self.root.len() ) );
+            }
+
+            flags |= FLAG_TRUNCATED_ROOT;
+            body.push( self.root.len() as u8 );
+        }
+        else if self.root.len() != 32 {
+            return Err( CompactError::InvalidRootPrefix( self.root.len() ) );
+        }
+
+        body.extend( &self.root );
+
+        write_varint( &mut body, self.proof.len() as u64 );
+        for hash in self.proof.iter() {
+            body.extend( hash );
+        }
+
+        write_bytes( &mut body, self.label.as_bytes() );
+        write_bytes( &mut body, &self.salt );
+        write_bytes( &mut body, &self.value );
+
+        let deflated = miniz_oxide::deflate::compress_to_vec( &body, 10 );
+
+        if deflated.len() < body.len() {
+            flags |= FLAG_DEFLATED;
+            body = deflated;
+        }
+
+        let mut bytes = Vec::with_capacity( HEADER_SIZE + body.len() );
+        bytes.push( COMPACT_VERSION );
+        bytes.push( flags );
+        bytes.extend( body );
+
+        match budget {
+            Some(budget) if bytes.len() > budget => Err( CompactError::OverBudget {
+                size: bytes.len(),
+                budget,
+            }),
+            _ => Ok( bytes ),
+        }
+    }
+
+    /// Decode a proof made by [`CompactProof::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CompactError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err( CompactError::UnexpectedEnd );
+        }
+
+        if bytes[0] != COMPACT_VERSION {
+            return Err( CompactError::UnsupportedVersion( bytes[0] ) );
+        }
+
+        let flags = bytes[1];
+
+        if flags & !(FLAG_TRUNCATED_ROOT | FLAG_DEFLATED) != 0 {
+            return Err( CompactError::UnknownFlags( flags ) );
+        }
+
+        let body = if flags & FLAG_DEFLATED != 0 {
+            miniz_oxide::inflate::decompress_to_vec_with_limit( &bytes[HEADER_SIZE..], MAX_BODY_SIZE )
+                .map_err(|err| CompactError::Inflate( err.to_string() ) )?
+        } else {
+            bytes[HEADER_SIZE..].to_vec()
+        };
+        let mut reader = Reader { bytes: &body };
+
+        let index = reader.varint()?;
+        let total_leaves = reader.varint()?;
+        let root_length = match flags & FLAG_TRUNCATED_ROOT != 0 {
+            true => match reader.take( 1 )?[0] as usize {
+                length if (MIN_ROOT_PREFIX..32).contains( &length ) => length,
+                length => return Err( CompactError::InvalidRootPrefix( length ) ),
+            },
+            false => 32,
+        };
+        let root = reader.take( root_length )?.to_vec();
+
+        let proof_count = reader.varint()?;
+        let mut proof = Vec::new();
+        for _ in 0..proof_count {
+            proof.push( <[u8; 32]>::try_from( reader.take( 32 )? ).unwrap() );
+        }
+
+        let label = String::from_utf8( reader.bytes()?.to_vec() )
+            .map_err(|_| CompactError::InvalidLabel )?;
+        let salt = reader.bytes()?.to_vec();
+        let value = reader.bytes()?.to_vec();
+
+        if !reader.bytes.is_empty() {
+            return Err( CompactError::TrailingBytes( reader.bytes.len() ) );
+        }
+
+        Ok(
+            CompactProof {
+                index,
+                total_leaves,
+                root,
+                proof,
+                label,
+                salt,
+                value,
+            }
+        )
+    }
+}
+
+
+/// Append an unsigned LEB128 varint
+pub fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push( (value as u8 & 0x7f) | 0x80 );
+        value >>= 7;
+    }
+
+    bytes.push( value as u8 );
+}
+
+
+fn write_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_varint( bytes, data.len() as u64 );
+    bytes.extend( data );
+}
+
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], CompactError> {
+        if self.bytes.len() < length {
+            return Err( CompactError::UnexpectedEnd );
+        }
+
+        let (taken, rest) = self.bytes.split_at( length );
+        self.bytes = rest;
+
+        Ok( taken )
+    }
+
+    fn varint(&mut self) -> Result<u64, CompactError> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by( 7 ) {
+            let byte = self.take( 1 )?[0];
+
+            if shift == 63 && byte > 1 {
+                return Err( CompactError::InvalidVarint );
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok( value );
+            }
+        }
+
+        Err( CompactError::InvalidVarint )
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], CompactError> {
+        let length = self.varint()?;
+
+        self.take( usize::try_from( length ).map_err(|_| CompactError::UnexpectedEnd )? )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{ Sha256, Digest };
+
+    fn hash(seed: u8) -> [u8; 32] {
+        Sha256::digest( [ seed ] ).into()
+    }
+
+    fn proof() -> CompactProof {
+        CompactProof {
+            index: 300,
+            total_leaves: 1024,
+            root: hash( 0 ).to_vec(),
+            proof: vec![ hash( 1 ), hash( 2 ), hash( 3 ), hash( 4 ) ],
+            label: "date_of_birth".to_string(),
+            salt: hash( 5 ).to_vec(),
+            value: vec![ 0xcf, 0, 0, 0, 13, 210, 35, 4, 0 ],
+        }
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [ 0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX ].iter() {
+            let mut bytes = Vec::new();
+            write_varint( &mut bytes, *value );
+
+            assert_eq!( Reader { bytes: &bytes }.varint().unwrap(), *value );
+        }
+
+        let mut bytes = Vec::new();
+        write_varint( &mut bytes, 300 );
+        assert_eq!( bytes, vec![ 0xac, 0x02 ] );
+
+        assert_eq!( Reader { bytes: &[ 0xff; 11 ] }.varint(), Err(CompactError::InvalidVarint) );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = proof().to_bytes( None ).unwrap();
+
+        assert_eq!( bytes[0], COMPACT_VERSION );
+        assert_eq!( bytes[1], 0 );
+        assert_eq!( CompactProof::from_bytes( &bytes ).unwrap(), proof() );
+    }
+
+    #[test]
+    fn test_truncated_root() {
+        let mut truncated = proof();
+        truncated.root.truncate( 8 );
+
+        let bytes = truncated.to_bytes( None ).unwrap();
+
+        assert!( bytes[1] & FLAG_TRUNCATED_ROOT != 0 );
+        assert!( bytes.len() < proof().to_bytes( None ).unwrap().len() );
+        assert_eq!( CompactProof::from_bytes( &bytes ).unwrap(), truncated );
+
+        truncated.root.truncate( 4 );
+        assert_eq!( truncated.to_bytes( None ), Err(CompactError::InvalidRootPrefix(4)) );
+    }
+
+    #[test]
+    fn test_deflate() {
+        let mut repetitive = proof();
+        repetitive.value = vec![ 0xc0; 200 ];
+
+        let bytes = repetitive.to_bytes( None ).unwrap();
+
+        assert!( bytes[1] & FLAG_DEFLATED != 0 );
+        assert_eq!( CompactProof::from_bytes( &bytes ).unwrap(), repetitive );
+    }
+
+    #[test]
+    fn test_budget() {
+        let size = proof().to_bytes( None ).unwrap().len();
+
+        assert!( proof().to_bytes( Some( size ) ).is_ok() );
+        assert_eq!( proof().to_bytes( Some( size - 1 ) ), Err(CompactError::OverBudget {
+            size,
+            budget: size - 1,
+        }) );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = proof().to_bytes( None ).unwrap();
+
+        assert_eq!( CompactProof::from_bytes( &[ 2, 0 ] ), Err(CompactError::UnsupportedVersion(2)) );
+        assert_eq!( CompactProof::from_bytes( &[ 1, 0x80 ] ), Err(CompactError::UnknownFlags(0x80)) );
+        assert_eq!( CompactProof::from_bytes( &bytes[..bytes.len() - 1] ), Err(CompactError::UnexpectedEnd) );
+
+        let mut trailing = bytes.clone();
+        trailing.push( 0 );
+        assert_eq!( CompactProof::from_bytes( &trailing ), Err(CompactError::TrailingBytes(1)) );
+    }
+}
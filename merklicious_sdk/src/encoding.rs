@@ -0,0 +1,144 @@
+//! Text encodings for sharing hashes and keys outside of Holochain
+//!
+//! Only the alphabets needed by this crate are implemented so the SDK does not need extra
+//! dependencies.
+
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+
+/// Encode bytes using the Bitcoin base58 alphabet
+pub fn base58btc_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|byte| **byte == 0 ).count();
+    // Little-endian base58 digits
+    let mut digits : Vec<u8> = Vec::new();
+
+    for byte in bytes[zeros..].iter() {
+        let mut carry = *byte as u32;
+
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits.push( (carry % 58) as u8 );
+            carry /= 58;
+        }
+    }
+
+    let mut text = "1".repeat( zeros );
+    text.extend( digits.iter().rev().map(|digit| BASE58_ALPHABET[ *digit as usize ] as char ) );
+
+    text
+}
+
+
+/// Decode a string that uses the Bitcoin base58 alphabet
+pub fn base58btc_decode(text: &str) -> Option<Vec<u8>> {
+    let zeros = text.bytes().take_while(|c| *c == b'1' ).count();
+    // Little-endian bytes
+    let mut bytes : Vec<u8> = Vec::new();
+
+    for c in text.bytes().skip( zeros ) {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c )? as u32;
+
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            bytes.push( (carry & 0xff) as u8 );
+            carry >>= 8;
+        }
+    }
+
+    let mut decoded = vec![ 0; zeros ];
+    decoded.extend( bytes.into_iter().rev() );
+
+    Some( decoded )
+}
+
+
+
+/// Encode bytes using the URL safe base64 alphabet without padding
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity( bytes.len() / 3 * 4 + 4 );
+
+    for chunk in bytes.chunks( 3 ) {
+        let group = chunk.iter()
+            .enumerate()
+            .fold( 0u32, |group, (i, byte)| group | ((*byte as u32) << (16 - 8 * i)) );
+
+        for i in 0..=chunk.len() {
+            text.push( BASE64URL_ALPHABET[ ((group >> (18 - 6 * i)) & 0x3f) as usize ] as char );
+        }
+    }
+
+    text
+}
+
+
+/// Decode a string that uses the URL safe base64 alphabet (padding is optional)
+pub fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity( text.len() * 3 / 4 );
+
+    for chunk in text.as_bytes().chunks( 4 ) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut group = 0u32;
+
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64URL_ALPHABET.iter().position(|a| a == c )? as u32;
+            group |= value << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            bytes.push( (group >> (16 - 8 * i)) as u8 );
+        }
+    }
+
+    Some( bytes )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58btc_round_trip() {
+        assert_eq!( base58btc_encode( b"" ), "" );
+        assert_eq!( base58btc_encode( b"hello world" ), "StV1DL6CwTryKyV" );
+        assert_eq!( base58btc_encode( &[0, 0, 1] ), "112" );
+
+        for bytes in [ &b"hello world"[..], &[0, 0, 1], &[255; 34] ].iter() {
+            assert_eq!( base58btc_decode( &base58btc_encode( bytes ) ).unwrap(), bytes.to_vec() );
+        }
+
+        assert_eq!( base58btc_decode( "0OIl" ), None );
+    }
+
+    #[test]
+    fn test_base64url_round_trip() {
+        assert_eq!( base64url_encode( b"" ), "" );
+        assert_eq!( base64url_encode( b"f" ), "Zg" );
+        assert_eq!( base64url_encode( b"fo" ), "Zm8" );
+        assert_eq!( base64url_encode( b"foo" ), "Zm9v" );
+        assert_eq!( base64url_encode( &[251, 255] ), "-_8" );
+
+        for bytes in [ &b"foobar"[..], &[0, 0, 1], &[255; 34] ].iter() {
+            assert_eq!( base64url_decode( &base64url_encode( bytes ) ).unwrap(), bytes.to_vec() );
+        }
+
+        assert_eq!( base64url_decode( "Zm8=" ).unwrap(), b"fo".to_vec() );
+        assert_eq!( base64url_decode( "Zm9v+" ), None );
+    }
+}
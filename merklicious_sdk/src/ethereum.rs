@@ -0,0 +1,274 @@
+//! Ethereum compatible Merkle trees
+//!
+//! The tree matches OpenZeppelin's `StandardMerkleTree` (`@openzeppelin/merkle-tree`) so its root
+//! and proofs can be checked on-chain with `MerkleProof.verify`.
+//!
+//! - Leaves are `keccak256(keccak256(abi.encode(...values)))`
+//! - Pairs are hashed in sorted order, so proofs don't need left/right flags
+//! - Leaves are sorted by hash and stored at the end of a flat array in reverse order, with each
+//!   node `i` being the parent of nodes `2i + 1` and `2i + 2`
+
+use sha3::{ Digest, Keccak256 };
+
+/// The `format` of an OpenZeppelin `StandardMerkleTree` dump
+pub const STANDARD_TREE_FORMAT: &str = "standard-v1";
+
+/// The Solidity types of each leaf's values (label, msgpack encoded value and salt)
+pub const LEAF_ENCODING: [&str; 3] = [ "string", "bytes", "bytes" ];
+
+
+/// The Keccak-256 hash used by Ethereum (not the padded NIST SHA3-256)
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest( bytes ).into()
+}
+
+
+fn abi_word(value: usize) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[ 24.. ].copy_from_slice( &(value as u64).to_be_bytes() );
+    word
+}
+
+
+/// Solidity's `abi.encode` for a list of dynamic `bytes` or `string` values
+pub fn abi_encode_dynamic(values: &[&[u8]]) -> Vec<u8> {
+    let mut head = Vec::with_capacity( values.len() * 32 );
+    let mut tail = Vec::new();
+
+    for value in values.iter() {
+        head.extend( &abi_word( values.len() * 32 + tail.len() ) );
+        tail.extend( &abi_word( value.len() ) );
+        tail.extend( *value );
+        tail.resize( tail.len() + (32 - tail.len() % 32) % 32, 0 );
+    }
+
+    head.extend( tail );
+    head
+}
+
+
+/// Hash ABI encoded leaf values the way `StandardMerkleTree` does
+pub fn leaf_hash(abi_encoded: &[u8]) -> [u8; 32] {
+    keccak256( &keccak256( abi_encoded ) )
+}
+
+
+/// Hash two nodes in sorted order (OpenZeppelin's `_hashPair`)
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity( 64 );
+
+    if a <= b {
+        bytes.extend( a );
+        bytes.extend( b );
+    } else {
+        bytes.extend( b );
+        bytes.extend( a );
+    }
+
+    keccak256( &bytes )
+}
+
+
+/// Compute the root that a leaf and proof lead to (OpenZeppelin's `MerkleProof.processProof`)
+pub fn process_proof(leaf: &[u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter()
+        .fold( *leaf, |computed, sibling| hash_pair( &computed, sibling ) )
+}
+
+
+/// Check a proof the way `MerkleProof.verify` does on-chain
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], proof: &[[u8; 32]]) -> bool {
+    process_proof( leaf, proof ) == *root
+}
+
+
+/// A Merkle tree laid out like OpenZeppelin's `StandardMerkleTree`
+#[derive(Clone, Debug, PartialEq)]
+pub struct StandardMerkleTree {
+    /// Every node, starting with the root
+    pub tree: Vec<[u8; 32]>,
+    /// The position in `tree` of each leaf, in the order the leaves were given
+    pub tree_indices: Vec<usize>,
+}
+
+impl StandardMerkleTree {
+    /// Build a tree from leaf hashes (there must be at least 1 leaf)
+    pub fn from_leaves(leaves: &[[u8; 32]]) -> Option<Self> {
+        if leaves.is_empty() {
+            return None;
+        }
+
+        let mut sorted : Vec<(usize, [u8; 32])> = leaves.iter().cloned().enumerate().collect();
+        sorted.sort_by_key(|(_, leaf)| *leaf );
+
+        let size = 2 * leaves.len() - 1;
+        let mut tree = vec![ [0; 32]; size ];
+        let mut tree_indices = vec![ 0; leaves.len() ];
+
+        for (position, (leaf_index, leaf)) in sorted.into_iter().enumerate() {
+            tree[ size - 1 - position ] = leaf;
+            tree_indices[ leaf_index ] = size - 1 - position;
+        }
+
+        for index in (0..size - leaves.len()).rev() {
+            tree[ index ] = hash_pair( &tree[ 2 * index + 1 ], &tree[ 2 * index + 2 ] );
+        }
+
+        Some( StandardMerkleTree { tree, tree_indices } )
+    }
+
+    /// The root hash
+    pub fn root(&self) -> [u8; 32] {
+        self.tree[0]
+    }
+
+    /// Get the proof for the leaf given at `leaf_index`
+    pub fn proof(&self, leaf_index: usize) -> Option<Vec<[u8; 32]>> {
+        let mut index = *self.tree_indices.get( leaf_index )?;
+        let mut proof = Vec::new();
+
+        while index > 0 {
+            let sibling = match index % 2 {
+                1 => index + 1,
+                _ => index - 1,
+            };
+            proof.push( self.tree[ sibling ] );
+            index = (index - 1) / 2;
+        }
+
+        Some( proof )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn from_hex(text: &str) -> Vec<u8> {
+        hex::decode( text.trim_start_matches("0x") ).unwrap()
+    }
+
+    fn hash_from_hex(text: &str) -> [u8; 32] {
+        let mut hash = [0; 32];
+        hash.copy_from_slice( &from_hex( text ) );
+        hash
+    }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!( hex::encode( keccak256( b"" ) ), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470" );
+        assert_eq!( hex::encode( keccak256( b"abc" ) ), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45" );
+        // Longer than the rate so more than one block is absorbed
+        assert_eq!( hex::encode( keccak256( &[ b'a'; 200 ] ) ), "96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d" );
+    }
+
+    #[test]
+    fn test_abi_encode_dynamic() {
+        let encoded = abi_encode_dynamic( &[ b"abc", b"" ] );
+
+        assert_eq!( encoded.len(), 32 * 5 );
+        assert_eq!( encoded[ 31 ], 64 );
+        assert_eq!( encoded[ 63 ], 128 );
+        assert_eq!( encoded[ 95 ], 3 );
+        assert_eq!( &encoded[ 96..99 ], b"abc" );
+        assert_eq!( encoded[ 159 ], 0 );
+    }
+
+    #[test]
+    fn test_openzeppelin_readme_tree() {
+        // The example from the @openzeppelin/merkle-tree README with [ "address", "uint256" ]
+        let leaves : Vec<[u8; 32]> = [
+            ( "1111111111111111111111111111111111111111", 5_000_000_000_000_000_000u128 ),
+            ( "2222222222222222222222222222222222222222", 2_500_000_000_000_000_000u128 ),
+        ].iter()
+            .map(|(address, amount)| {
+                let mut encoded = vec![ 0; 12 ];
+                encoded.extend( from_hex( address ) );
+                encoded.extend( &[0; 16] );
+                encoded.extend( &amount.to_be_bytes() );
+                leaf_hash( &encoded )
+            })
+            .collect();
+        let tree = StandardMerkleTree::from_leaves( &leaves ).unwrap();
+
+        assert_eq!( hex::encode( tree.root() ), "d4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77" );
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert!( verify( &tree.root(), leaf, &tree.proof( index ).unwrap() ) );
+        }
+    }
+
+    #[test]
+    fn test_proofs() {
+        assert_eq!( StandardMerkleTree::from_leaves( &[] ), None );
+
+        for count in 1..10u8 {
+            let leaves : Vec<[u8; 32]> = (0..count).map(|i| keccak256( &[i] ) ).collect();
+            let tree = StandardMerkleTree::from_leaves( &leaves ).unwrap();
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof( index ).unwrap();
+
+                assert!( verify( &tree.root(), leaf, &proof ) );
+                assert!( !verify( &tree.root(), &keccak256( b"other" ), &proof ) );
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct VectorValue {
+        value: Vec<String>,
+        #[serde(rename = "treeIndex")]
+        tree_index: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct VectorDump {
+        format: String,
+        tree: Vec<String>,
+        values: Vec<VectorValue>,
+        #[serde(rename = "leafEncoding")]
+        leaf_encoding: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Vector {
+        dump: VectorDump,
+        root: String,
+        proofs: Vec<Vec<String>>,
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors : Vec<Vector> = serde_json::from_str( include_str!("../../tests/vectors/ethereum_standard_merkle_tree.json") ).unwrap();
+
+        for vector in vectors.iter() {
+            assert_eq!( vector.dump.format, STANDARD_TREE_FORMAT );
+            assert_eq!( vector.dump.leaf_encoding, LEAF_ENCODING.to_vec() );
+
+            let leaves : Vec<[u8; 32]> = vector.dump.values.iter()
+                .map(|value| {
+                    let label = value.value[0].as_bytes().to_vec();
+                    let encoded = abi_encode_dynamic( &[ &label, &from_hex( &value.value[1] ), &from_hex( &value.value[2] ) ] );
+                    leaf_hash( &encoded )
+                })
+                .collect();
+            let tree = StandardMerkleTree::from_leaves( &leaves ).unwrap();
+            let root = hash_from_hex( &vector.root );
+
+            assert_eq!( tree.root(), root );
+            assert_eq!( tree.tree.iter().map(|node| format!("0x{}", hex::encode( node ) ) ).collect::<Vec<String>>(), vector.dump.tree );
+
+            for (index, (leaf, proof)) in leaves.iter().zip( vector.proofs.iter() ).enumerate() {
+                let proof : Vec<[u8; 32]> = proof.iter().map(|hash| hash_from_hex( hash ) ).collect();
+
+                assert_eq!( tree.tree_indices[ index ], vector.dump.values[ index ].tree_index );
+                assert_eq!( tree.proof( index ).unwrap(), proof );
+                assert!( verify( &root, leaf, &proof ) );
+            }
+        }
+    }
+}
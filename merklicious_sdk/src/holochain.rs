@@ -0,0 +1,1770 @@
+//! Entry types, payloads and zome helpers that depend on the HDK
+//!
+//! Everything here is re-exported from the crate root when the `hdk` feature is enabled.
+
+use std::collections::BTreeMap;
+use crate::hdi;
+use crate::hdi_extensions::guest_error;
+use hdi::prelude::*;
+use crate::hdk::prelude::sys_time;
+use sha2::{ Sha256, Digest };
+use rs_merkle::{ MerkleProof, algorithms };
+use crate::{
+    bundle,
+    compact,
+    ethereum,
+    HashAlgorithm,
+    merkle_hash,
+    merkle_layers,
+    merkle_log,
+    mmr,
+    sd_jwt,
+    sparse_merkle_tree,
+    transport,
+    verify,
+    MerkleHash,
+    SparseMerkleProof,
+    LeafDataBlock,
+    LeafInput,
+    LeafProofPayload,
+    PaddingMode,
+    TreeType,
+};
+
+
+/// The default maximum number of leaves stored in a single tree entry before the tree is split
+/// into chunks
+pub const DEFAULT_CHUNK_SIZE: u64 = 1024;
+
+
+
+//
+// General Functions
+//
+/// Get a current timestamp according to the HDK's [`sys_time`]
+pub fn now() -> ExternResult<u64> {
+    sys_time()
+	.map( |t| (t.as_micros() / 1000) as u64 )
+}
+
+/// Serialize the given data using [`rmp_serde`] and return the SHA-256 hash
+pub fn sha256<T>(data: &T) -> ExternResult<[u8; 32]>
+where
+    T: Serialize + std::fmt::Debug,
+{
+    let bytes = rmp_serde::to_vec( &data )
+        .or(Err(guest_error!(format!("Failed to serialize input; {:#?}", data))))?;
+    let mut hasher = Sha256::new();
+    hasher.update( &bytes );
+    Ok(
+        <[u8; 32]>::from( hasher.finalize() )
+    )
+}
+
+impl From<transport::TransportError> for WasmError {
+    fn from(error: transport::TransportError) -> Self {
+        guest_error!(format!("{}", error ))
+    }
+}
+
+impl From<compact::CompactError> for WasmError {
+    fn from(error: compact::CompactError) -> Self {
+        guest_error!(format!("{}", error ))
+    }
+}
+
+impl From<merkle_hash::MerkleHashError> for WasmError {
+    fn from(error: merkle_hash::MerkleHashError) -> Self {
+        guest_error!(format!("{}", error ))
+    }
+}
+
+impl From<verify::VerifyError> for WasmError {
+    fn from(error: verify::VerifyError) -> Self {
+        guest_error!(format!("{}", error ))
+    }
+}
+
+impl From<bundle::BundleError> for WasmError {
+    fn from(error: bundle::BundleError) -> Self {
+        guest_error!(format!("{}", error ))
+    }
+}
+
+
+
+// Trait for common fields
+/// Common fields that are expected on some entry structs
+pub trait CommonFields<'a> {
+    /// A spot for holding data that is not relevant to integrity validation
+    fn metadata(&'a self) -> &'a BTreeMap<String, rmpv::Value>;
+}
+
+/// Auto-implement the [`CommonFields`] trait
+///
+/// The input must be a struct with fields matching each common field method.
+///
+/// #### Example
+/// ```ignore
+/// struct PostEntry {
+///     pub message: String,
+///
+///     // Common fields
+///     pub metadata: BTreeMap<String, rmpv::Value>,
+/// }
+/// common_fields!( PostEntry );
+/// ```
+#[macro_export]
+macro_rules! common_fields {
+    ( $name:ident ) => {
+        impl<'a> CommonFields<'a> for $name {
+            fn metadata(&'a self) -> &'a BTreeMap<String, rmpv::Value> {
+                &self.metadata
+            }
+        }
+    };
+}
+
+
+
+//
+// Common Structs
+//
+impl LeafDataBlock {
+    /// Get the values of this block's Ethereum leaf (label, msgpack encoded value and salt)
+    ///
+    /// See [`ethereum::LEAF_ENCODING`] for their Solidity types.
+    pub fn ethereum_values(&self) -> ExternResult<[Vec<u8>; 3]> {
+        let value = rmp_serde::to_vec( &self.value )
+            .map_err(|err| guest_error!(format!("Failed to encode value of '{}': {:?}", self.label, err )))?;
+
+        Ok([ self.label.as_bytes().to_vec(), value, self.salt.clone() ])
+    }
+
+    /// ABI encode this block's Ethereum leaf values
+    pub fn abi_encode(&self) -> ExternResult<Vec<u8>> {
+        let [ label, value, salt ] = self.ethereum_values()?;
+
+        Ok( ethereum::abi_encode_dynamic( &[ &label, &value, &salt ] ) )
+    }
+}
+
+impl LeafProofPayload {
+    /// Encode this proof with [`compact`] for QR codes
+    ///
+    /// A `root_prefix` truncates the root to that many bytes, which is insecure (see [`compact`]).
+    pub fn to_compact(&self, root_prefix: Option<usize>, budget: Option<usize>) -> ExternResult<Vec<u8>> {
+        if !self.hash_algorithm.is_sha256() {
+            return Err(guest_error!(format!("Compact proofs only support {:?} trees; not {:?}", HashAlgorithm::Sha256, self.hash_algorithm )));
+        }
+
+        let root = match root_prefix {
+            Some(length) => self.root[ ..length.min( 32 ) ].to_vec(),
+            None => self.root.to_vec(),
+        };
+        let value = rmp_serde::to_vec( &self.target.value )
+            .map_err(|err| guest_error!(format!("Failed to encode value of '{}': {:?}", self.target.label, err )))?;
+
+        Ok(
+            compact::CompactProof {
+                index: self.index,
+                total_leaves: self.total_leaves,
+                root,
+                proof: MerkleHash::to_raw_vec( &self.proof ),
+                label: self.target.label.clone(),
+                salt: self.target.salt.clone(),
+                value,
+            }.to_bytes( budget )?
+        )
+    }
+
+    /// Rebuild a full proof from [`LeafProofPayload::to_compact`] bytes
+    ///
+    /// The leaf is recomputed from the data block.  A truncated root can't be trusted, so it needs
+    /// the full `expected_root` from somewhere else; the root recomputed from the proof must match it
+    /// and start with the encoded prefix.  A full root must match `expected_root` when it is given.
+    pub fn from_compact(bytes: &[u8], expected_root: Option<&[u8; 32]>) -> ExternResult<Self> {
+        let compact = compact::CompactProof::from_bytes( bytes )?;
+        let target = LeafDataBlock {
+            value: rmp_serde::from_slice( &compact.value )
+                .map_err(|err| guest_error!(format!("Invalid value for '{}': {:?}", compact.label, err )))?,
+            label: compact.label,
+            salt: compact.salt,
+        };
+        let leaf = target.hash()?;
+        let root = match (compact.root.len(), expected_root) {
+            (32, None) => MerkleHash::from_slice( &compact.root )?.0,
+            (32, Some(expected_root)) => {
+                if compact.root != expected_root {
+                    return Err(guest_error!(format!("Proof root does not match the expected root {}", hex::encode( expected_root ) )));
+                }
+
+                *expected_root
+            },
+            (length, None) => {
+                return Err(guest_error!(format!("Proof root is truncated to {} bytes; the expected root is required", length )));
+            },
+            (_, Some(expected_root)) => {
+                let root = MerkleProof::<algorithms::Sha256>::new( compact.proof.clone() )
+                    .root( &[ compact.index as usize ], &[ leaf ], compact.total_leaves as usize )
+                    .map_err(|err| guest_error!(format!("Failed to compute root: {}", err )))?;
+
+                if !root.starts_with( &compact.root ) || root != *expected_root {
+                    return Err(guest_error!(format!("Proof does not lead to the expected root {}", hex::encode( expected_root ) )));
+                }
+
+                root
+            },
+        };
+
+        Ok(
+            LeafProofPayload {
+                proof: MerkleHash::from_raw_vec( compact.proof ),
+                index: compact.index,
+                target,
+                leaf: leaf.into(),
+                root: root.into(),
+                total_leaves: compact.total_leaves,
+                hash_algorithm: HashAlgorithm::Sha256,
+            }
+        )
+    }
+}
+
+/// All the information required to verify a label against a sparse Merkle tree
+///
+/// When `target` is `None` the proof shows that the tree has no leaf for `label`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SparseLeafProofPayload {
+    /// The compressed sparse Merkle proof
+    pub proof: SparseMerkleProof,
+    /// The label that the proof is for
+    pub label: String,
+    /// The revealed leaf data (inclusion proofs only)
+    pub target: Option<LeafDataBlock>,
+    /// The sha256 hash of the target leaf (inclusion proofs only)
+    pub leaf: Option<[u8; 32]>,
+    /// The sparse Merkle tree's root hash
+    pub root: [u8; 32],
+}
+
+
+
+//
+// Tree Entry
+//
+/// An entry struct for storing the leaf data blocks that were used to create a tree
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct DataBlocksEntry {
+    /// A list of leaf data blocks
+    pub blocks: Vec<LeafDataBlock>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( DataBlocksEntry );
+
+
+/// An entry struct for storing the intermediate layers of a standard Merkle tree
+///
+/// See [`merkle_layers`] for how the layers are built and read.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct TreeLayersEntry {
+    /// Every layer above the leaves, ending with the layer that contains only the root
+    pub layers: Vec<Vec<[u8; 32]>>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( TreeLayersEntry );
+
+
+/// An entry struct for storing one chunk of a large tree's leaf hashes
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct LeafChunkEntry {
+    /// The leaf hashes in this chunk
+    pub leaves: Vec<[u8; 32]>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( LeafChunkEntry );
+
+
+/// A reference to one chunk of a large tree
+///
+/// Each chunk's data blocks are stored in their own [`DataBlocksEntry`] and its leaf hashes in a
+/// [`LeafChunkEntry`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreeChunk {
+    /// The data blocks in this chunk (`None` when the chunk only contains padding leaves)
+    pub data_blocks: Option<ActionHash>,
+    /// The leaf hashes in this chunk
+    pub leaves: ActionHash,
+    /// The labels of this chunk's data blocks, in order
+    pub labels: Vec<String>,
+    /// The number of leaves in this chunk
+    pub size: u64,
+    /// The Merkle root of this chunk's leaves
+    pub root: [u8; 32],
+}
+
+
+/// An entry struct that represents a Merkle tree
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct TreeEntry {
+    /// The leaf data blocks used to create this tree (`None` when the tree is chunked)
+    pub data_blocks: Option<ActionHash>,
+    /// The leaf hashes of this Merkle tree (empty when the tree is chunked)
+    pub leaves: Vec<MerkleHash>,
+    /// A secret entropy used for creating deterministic salts
+    pub entropy: Vec<u8>,
+    /// The root hash of this Merkle tree
+    pub root: MerkleHash,
+    /// The kind of Merkle tree that `root` belongs to
+    #[serde(default)]
+    pub tree_type: TreeType,
+    /// The precomputed intermediate layers of this tree (see [`TreeLayersEntry`])
+    #[serde(default)]
+    pub layers: Option<ActionHash>,
+    /// The chunks that make up a large tree, in order
+    #[serde(default)]
+    pub chunks: Vec<TreeChunk>,
+    /// The chunk size this tree was created with (see [`TreeEntry::effective_chunk_size`])
+    #[serde(default)]
+    pub chunk_size: Option<u64>,
+    /// The hash function used for this tree's leaves and nodes
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( TreeEntry );
+
+impl TreeEntry {
+    /// Get the Merkle tree root as a hex string
+    pub fn root_as_hex(&self) -> String {
+        self.root.to_hex()
+    }
+
+    /// Check if this tree's data blocks and leaves are stored in chunks
+    pub fn is_chunked(&self) -> bool {
+        !self.chunks.is_empty()
+    }
+
+    /// Get the total number of leaves in this tree, including padding
+    pub fn leaf_count(&self) -> u64 {
+        match self.is_chunked() {
+            true => self.chunks.iter().map(|chunk| chunk.size ).sum(),
+            false => self.leaves.len() as u64,
+        }
+    }
+
+    /// Get a chunk size that recreates this tree with the same layout
+    ///
+    /// Trees created before `chunk_size` was stored fall back to the size of their first chunk, or
+    /// the smallest power of two that fits all of their leaves in one chunk.
+    pub fn effective_chunk_size(&self) -> u64 {
+        match (self.chunk_size, self.chunks.first()) {
+            (Some(chunk_size), _) => chunk_size,
+            (None, Some(chunk)) => chunk.size,
+            (None, None) => self.leaf_count().next_power_of_two(),
+        }
+    }
+}
+
+
+
+//
+// Aggregate Tree Entry
+//
+/// A source for one leaf of an aggregate tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum AggregateSource {
+    /// A standard tree created by this agent
+    TreeId(ActionHash),
+    /// The root of any standard tree
+    Root([u8; 32]),
+}
+
+/// An entry struct that represents a tree whose leaves are the roots of other trees
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct AggregateTreeEntry {
+    /// The sub-tree roots used as leaves, in order
+    pub roots: Vec<[u8; 32]>,
+    /// The sub-tree create actions, when the sub-tree was given by ID
+    pub trees: Vec<Option<ActionHash>>,
+    /// The root hash of the aggregate tree
+    pub root: [u8; 32],
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( AggregateTreeEntry );
+
+impl AggregateTreeEntry {
+    /// Calculate the aggregate root of a list of sub-tree roots
+    pub fn calculate_root(roots: &[[u8; 32]]) -> Option<[u8; 32]> {
+        merkle_layers::root_from_layers( roots, &merkle_layers::build_layers( roots ) )
+    }
+
+    /// Chain a sub-tree leaf proof with the sub-tree's proof in this aggregate
+    pub fn chain_proof(&self, leaf_proof: LeafProofPayload) -> ExternResult<AggregateLeafProofPayload> {
+        let tree_index = self.roots.iter()
+            .position(|root| *root == leaf_proof.root )
+            .ok_or(guest_error!(format!("Aggregate tree does not include the sub-tree root {}", hex::encode( leaf_proof.root ) )))?;
+        let tree_proof = merkle_layers::proof_from_layers( &self.roots, &merkle_layers::build_layers( &self.roots ), tree_index )
+            .ok_or(guest_error!(format!("Couldn't create proof for sub-tree {}", tree_index )))?;
+
+        Ok(
+            AggregateLeafProofPayload {
+                leaf_proof,
+                tree_proof,
+                tree_index: tree_index as u64,
+                total_trees: self.roots.len() as u64,
+                root: self.root,
+            }
+        )
+    }
+}
+
+
+/// A leaf proof inside a sub-tree chained with the sub-tree's proof in an aggregate tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AggregateLeafProofPayload {
+    /// The proof of the leaf in its sub-tree
+    pub leaf_proof: LeafProofPayload,
+    /// The Merkle proof hash list for the sub-tree root in the aggregate tree
+    pub tree_proof: Vec<[u8; 32]>,
+    /// The sub-tree root's index in the aggregate tree
+    pub tree_index: u64,
+    /// The total number of sub-trees in the aggregate tree
+    pub total_trees: u64,
+    /// The aggregate tree's root hash
+    pub root: [u8; 32],
+}
+
+impl AggregateLeafProofPayload {
+    /// Verify the leaf against its sub-tree root and the sub-tree root against the aggregate root
+    pub fn verify(&self) -> ExternResult<bool> {
+        Ok(
+            self.leaf_proof.verify()?
+                && MerkleProof::<algorithms::Sha256>::new( self.tree_proof.clone() )
+                    .verify( self.root, &[ self.tree_index as usize ], &[ self.leaf_proof.root.0 ], self.total_trees as usize )
+        )
+    }
+}
+
+
+//
+// Log Entries
+//
+/// An entry struct that represents an append-only Merkle log
+///
+/// The log's leaves are stored in [`LogAppendEntry`]s that are linked from this entry.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct LogEntry {
+    /// A secret entropy used for creating deterministic salts
+    pub entropy: Vec<u8>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( LogEntry );
+
+
+/// An entry struct for a batch of leaves appended to a [`LogEntry`]
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct LogAppendEntry {
+    /// The create action of the log this batch belongs to
+    pub log: ActionHash,
+    /// The index of the first leaf in this batch
+    pub start: u64,
+    /// The leaf data blocks in this batch
+    pub data_blocks: Vec<LeafDataBlock>,
+    /// The [`merkle_log::leaf_hash`] of each data block's hash
+    pub leaves: Vec<[u8; 32]>,
+    /// The log size after this batch
+    pub size: u64,
+    /// The log root after this batch
+    pub root: [u8; 32],
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( LogAppendEntry );
+
+
+/// The current size and root of a Merkle log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogStatePayload {
+    /// The number of leaves in the log
+    pub size: u64,
+    /// The log's root hash
+    pub root: [u8; 32],
+}
+
+/// Proof that a Merkle log of `new_size` leaves is an extension of the same log at `old_size`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsistencyProofPayload {
+    /// The earlier log size
+    pub old_size: u64,
+    /// The later log size
+    pub new_size: u64,
+    /// The log root at `old_size`
+    pub old_root: [u8; 32],
+    /// The log root at `new_size`
+    pub new_root: [u8; 32],
+    /// The RFC 6962 consistency proof hashes
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl ConsistencyProofPayload {
+    /// Verify that `new_root` extends `old_root`
+    pub fn verify(&self) -> bool {
+        merkle_log::verify_consistency(
+            self.old_size, self.new_size, &self.old_root, &self.new_root, &self.proof
+        )
+    }
+}
+
+/// All the information required to verify a leaf of a Merkle log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogLeafProofPayload {
+    /// The RFC 6962 audit path
+    pub proof: Vec<[u8; 32]>,
+    /// The leaf's index in the log
+    pub index: u64,
+    /// The revealed leaf data
+    pub target: LeafDataBlock,
+    /// The log leaf hash of the target
+    pub leaf: [u8; 32],
+    /// The log root that the proof is for
+    pub root: [u8; 32],
+    /// The log size that the proof is for
+    pub size: u64,
+}
+
+impl LogLeafProofPayload {
+    /// Verify that `target` is the leaf at `index` of the log with `root`
+    pub fn verify(&self) -> ExternResult<bool> {
+        Ok(
+            merkle_log::leaf_hash( &self.target.hash()? ) == self.leaf
+                && merkle_log::verify_inclusion( self.index, self.size, &self.leaf, &self.proof, &self.root )
+        )
+    }
+}
+
+
+
+//
+// Merkle Mountain Range Entries
+//
+/// An entry struct that represents a Merkle Mountain Range accumulator
+///
+/// Each leaf is stored in its own [`MmrAppendEntry`] that is linked from this entry.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct MmrEntry {
+    /// A secret entropy used for creating deterministic salts
+    pub entropy: Vec<u8>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( MmrEntry );
+
+
+/// An entry struct for a single leaf appended to an [`MmrEntry`]
+///
+/// Along with the leaf, this stores every node that the append created and the peaks after the
+/// append, so the MMR never has to be rebuilt from its leaves.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct MmrAppendEntry {
+    /// The create action of the MMR this leaf belongs to
+    pub mmr: ActionHash,
+    /// The index of this leaf
+    pub index: u64,
+    /// The leaf data block
+    pub data_block: LeafDataBlock,
+    /// The nodes created by this append, by height (`nodes[0]` is the leaf hash)
+    pub nodes: Vec<[u8; 32]>,
+    /// The MMR peaks after this append
+    pub peaks: Vec<[u8; 32]>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( MmrAppendEntry );
+
+
+/// The current size, peaks and root of a Merkle Mountain Range
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MmrStatePayload {
+    /// The number of leaves in the MMR
+    pub size: u64,
+    /// The MMR peaks from left to right
+    pub peaks: Vec<[u8; 32]>,
+    /// The bagged peaks
+    pub root: [u8; 32],
+}
+
+/// All the information required to verify a leaf of a Merkle Mountain Range
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MmrProofPayload {
+    /// The sibling nodes from the leaf up to its peak
+    pub siblings: Vec<[u8; 32]>,
+    /// The MMR peaks that the proof is for
+    pub peaks: Vec<[u8; 32]>,
+    /// The leaf's index in the MMR
+    pub index: u64,
+    /// The MMR size that the proof is for
+    pub size: u64,
+    /// The revealed leaf data
+    pub target: LeafDataBlock,
+    /// The MMR leaf hash of the target
+    pub leaf: [u8; 32],
+    /// The MMR root that the proof is for
+    pub root: [u8; 32],
+}
+
+impl MmrProofPayload {
+    /// Verify that `target` is the leaf at `index` of the MMR with `root`
+    pub fn verify(&self) -> ExternResult<bool> {
+        Ok(
+            merkle_log::leaf_hash( &self.target.hash()? ) == self.leaf
+                && mmr::verify_inclusion( self.index, self.size, &self.leaf, &self.siblings, &self.peaks, &self.root )
+        )
+    }
+}
+
+
+
+//
+// Anchor Entry
+//
+/// A public entry where a witness commits to a batch of other agents' roots
+///
+/// The anchored roots are the leaves of a standard Merkle tree, so any one of them can be proven
+/// against the anchor's `root` with a short proof.  Every anchored root is public in `roots`, so
+/// anyone can see which roots were anchored together.  The anchor's create action timestamp is the
+/// time that the roots are known to have existed by.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct AnchorEntry {
+    /// The anchored roots, in order
+    pub roots: Vec<[u8; 32]>,
+    /// The Merkle root of `roots`
+    pub root: [u8; 32],
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( AnchorEntry );
+
+impl AnchorEntry {
+    /// Calculate the Merkle root of a list of roots
+    pub fn calculate_root(roots: &[[u8; 32]]) -> Option<[u8; 32]> {
+        merkle_layers::root_from_layers( roots, &merkle_layers::build_layers( roots ) )
+    }
+
+    /// Get the Merkle proof for the root at `index`
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        merkle_layers::proof_from_layers( &self.roots, &merkle_layers::build_layers( &self.roots ), index )
+    }
+}
+
+
+/// Proof that a root was included in an anchor created by `witness` at `anchored_at`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnchorProofPayload {
+    /// The create action of the anchor entry
+    pub anchor_id: ActionHash,
+    /// The agent that created the anchor
+    pub witness: AgentPubKey,
+    /// The anchor's create action timestamp
+    pub anchored_at: Timestamp,
+    /// The root that was anchored
+    pub root: [u8; 32],
+    /// The root's index in the anchor
+    pub index: u64,
+    /// The total number of roots in the anchor
+    pub total_roots: u64,
+    /// The Merkle proof hash list
+    pub proof: Vec<[u8; 32]>,
+    /// The anchor's Merkle root
+    pub anchor_root: [u8; 32],
+}
+
+impl AnchorProofPayload {
+    /// Verify that `root` is included in `anchor_root`
+    ///
+    /// This only checks the Merkle proof; the anchor record must be fetched to confirm `witness`
+    /// and `anchored_at`.
+    pub fn verify(&self) -> bool {
+        MerkleProof::<algorithms::Sha256>::new( self.proof.clone() )
+            .verify( self.anchor_root, &[ self.index as usize ], &[ self.root ], self.total_roots as usize )
+    }
+}
+
+
+//
+// Attestation Entry
+//
+/// A public entry where an issuer vouches for a holder's tree root
+///
+/// Relying parties check that a root is attested by an issuer they trust in addition to checking
+/// the Merkle proof.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct AttestationEntry {
+    /// The agent vouching for the root (must be the action author)
+    pub issuer: AgentPubKey,
+    /// The agent that holds the tree
+    pub holder: AgentPubKey,
+    /// The attested root
+    pub root: [u8; 32],
+    /// The issuer's signature of [`AttestationEntry::signed_content`]
+    pub signature: Signature,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( AttestationEntry );
+
+impl AttestationEntry {
+    /// Get the bytes that the issuer signs (see [`verify::attestation_message`])
+    pub fn signed_content(holder: &AgentPubKey, root: &[u8; 32]) -> Vec<u8> {
+        verify::attestation_message( holder.get_raw_39(), root )
+    }
+
+    /// Verify that `signature` was made by `issuer`
+    pub fn verify(&self) -> ExternResult<bool> {
+        verify_signature_raw(
+            self.issuer.clone(),
+            self.signature.clone(),
+            AttestationEntry::signed_content( &self.holder, &self.root ),
+        )
+    }
+}
+
+
+//
+// Credential Request Entry
+//
+/// An entry struct for a holder's request to be issued a credential tree
+///
+/// This is stored privately on the issuer's source chain until it is issued or rejected.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct CredentialRequestEntry {
+    /// The agent requesting the credential
+    pub holder: AgentPubKey,
+    /// The leaves that the holder is asking the issuer to vouch for
+    pub leaves: Vec<LeafInput>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( CredentialRequestEntry );
+
+
+/// An entry struct for a credential request that a holder has sent to an issuer
+///
+/// This is stored privately on the holder's source chain so that only credentials they asked for
+/// are accepted.  It is deleted once the credential is received.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct SentCredentialRequestEntry {
+    /// The agent that was asked to issue the credential
+    pub issuer: AgentPubKey,
+    /// The create action of the request on the issuer's source chain
+    pub request_id: ActionHash,
+    /// The leaves that the holder asked the issuer to vouch for
+    pub leaves: Vec<LeafInput>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( SentCredentialRequestEntry );
+
+
+/// A credential request that has not been issued or rejected
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingCredentialRequest {
+    /// The create action of the request on the issuer's source chain
+    pub request_id: ActionHash,
+    /// The request
+    pub request: CredentialRequestEntry,
+}
+
+/// The credential sent privately from an issuer to a holder
+///
+/// The holder recreates the tree from `data_blocks` and `entropy`, which must produce the same
+/// `root` that the attestation is for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssuedCredentialPayload {
+    /// The create action of the request on the issuer's source chain
+    pub request_id: ActionHash,
+    /// The entropy used for the tree's salts
+    pub entropy: Vec<u8>,
+    /// The tree's data blocks
+    pub data_blocks: Vec<LeafDataBlock>,
+    /// The tree's root
+    pub root: [u8; 32],
+    /// The issuer's attestation of `root`
+    pub attestation: AttestationEntry,
+}
+
+
+/// The result of issuing a credential
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CredentialIssuancePayload {
+    /// The issuer's copy of the tree
+    pub tree_id: ActionHash,
+    /// The holder's copy of the tree
+    pub holder_tree_id: ActionHash,
+    /// The attested root
+    pub root: [u8; 32],
+}
+
+/// Signals emitted to this agent's UI
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MerkliciousSignal {
+    /// A holder asked this agent to issue a credential
+    CredentialRequested {
+        request_id: ActionHash,
+        holder: AgentPubKey,
+    },
+    /// An issuer sent this agent a credential
+    CredentialReceived {
+        tree_id: ActionHash,
+        issuer: AgentPubKey,
+    },
+    /// A verifier asked this agent for proofs
+    ProofRequested {
+        request_id: ActionHash,
+        verifier: AgentPubKey,
+        labels: Vec<String>,
+    },
+    /// A holder answered this agent's proof request
+    ProofReceived {
+        presentation: PresentationPayload,
+    },
+    /// A holder denied this agent's proof request
+    ProofDenied {
+        request_id: ActionHash,
+        holder: AgentPubKey,
+    },
+}
+
+
+//
+// Proof Request Entry
+//
+/// An entry struct for a verifier's request for proofs of some labels
+///
+/// This is stored privately on the holder's source chain until it is approved or denied.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ProofRequestEntry {
+    /// The agent requesting the proofs
+    pub verifier: AgentPubKey,
+    /// The labels to prove
+    pub labels: Vec<String>,
+    /// The root that the proofs must be for (any tree if `None`)
+    pub root: Option<[u8; 32]>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( ProofRequestEntry );
+
+
+/// An entry struct for a proof request that a verifier has sent to a holder
+///
+/// This is stored privately on the verifier's source chain so that only answers to requests they
+/// made are accepted.  It is deleted once the request is answered.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct SentProofRequestEntry {
+    /// The agent that was asked for the proofs
+    pub holder: AgentPubKey,
+    /// The create action of the request on the holder's source chain
+    pub request_id: ActionHash,
+    /// The labels to prove
+    pub labels: Vec<String>,
+    /// The root that the proofs must be for (any tree if `None`)
+    pub root: Option<[u8; 32]>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( SentProofRequestEntry );
+
+
+/// A proof request that has not been approved or denied
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingProofRequest {
+    /// The create action of the request on the holder's source chain
+    pub request_id: ActionHash,
+    /// The request
+    pub request: ProofRequestEntry,
+}
+
+/// A set of leaf proofs for a single tree sent in response to a proof request
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentationPayload {
+    /// The create action of the request on the holder's source chain
+    pub request_id: ActionHash,
+    /// The agent presenting the proofs
+    pub holder: AgentPubKey,
+    /// The root of the presented tree
+    pub root: [u8; 32],
+    /// A proof for each requested label
+    pub proofs: Vec<LeafProofPayload>,
+}
+
+impl PresentationPayload {
+    /// Verify that every proof is valid and belongs to `root`
+    pub fn verify(&self) -> ExternResult<bool> {
+        for proof in self.proofs.iter() {
+            if proof.root != self.root || !proof.verify()? {
+                return Ok( false );
+            }
+        }
+
+        Ok( true )
+    }
+}
+
+
+/// Signals sent between agents with `remote_signal`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PeerSignal {
+    /// A holder approved a proof request
+    ProofResponse {
+        presentation: PresentationPayload,
+    },
+    /// A holder denied a proof request
+    ProofDenied {
+        request_id: ActionHash,
+    },
+}
+
+
+
+//
+// Disclosure Entry
+//
+/// An entry struct recording which labels of a tree were revealed to another agent
+///
+/// This is stored privately on the holder's source chain so they can audit what they've shared.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct DisclosureEntry {
+    /// The agent the proofs were generated for
+    pub recipient: AgentPubKey,
+    /// The labels that were revealed
+    pub labels: Vec<String>,
+    /// The create action of the tree that the proofs belong to
+    pub tree: ActionHash,
+    /// When the proofs were generated
+    pub disclosed_at: Timestamp,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( DisclosureEntry );
+
+
+
+//
+// Delegation Entry
+//
+/// An entry struct for the scope of a proof delegation
+///
+/// The delegate can only call the proof externs through a cap grant whose tag refers to this entry
+/// (see [`DelegationEntry::grant_tag`]).
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct DelegationEntry {
+    /// The agent allowed to request proofs
+    pub delegate: AgentPubKey,
+    /// The create action of the tree that proofs can be generated from
+    pub tree: ActionHash,
+    /// The labels that can be proven
+    pub labels: Vec<String>,
+    /// When the delegation stops being honoured (never if `None`)
+    pub expires_at: Option<Timestamp>,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( DelegationEntry );
+
+impl DelegationEntry {
+    /// The cap grant tag used for the delegation with the given create action
+    pub fn grant_tag(delegation_id: &ActionHash) -> String {
+        format!("delegation:{}", delegation_id )
+    }
+
+    /// Check if `delegate` may get a proof for `label` in `tree_id` at time `now`
+    pub fn allows(&self, delegate: &AgentPubKey, tree_id: &ActionHash, label: &str, now: &Timestamp) -> bool {
+        self.delegate == *delegate
+            && self.tree == *tree_id
+            && self.labels.iter().any(|allowed| allowed == label )
+            && self.expires_at.is_none_or(|expires_at| *now < expires_at )
+    }
+}
+
+
+/// A delegation that has not been revoked
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofDelegation {
+    /// The create action of the delegation entry
+    pub delegation_id: ActionHash,
+    /// The delegation scope
+    pub delegation: DelegationEntry,
+}
+
+/// The result of granting a proof delegation
+///
+/// The `cap_secret` must be given to the delegate so they can call the proof externs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofDelegationPayload {
+    /// The create action of the delegation entry
+    pub delegation_id: ActionHash,
+    /// The secret for the delegation's cap grant
+    pub cap_secret: CapSecret,
+}
+
+
+
+//
+// Encrypted Tree Entry
+//
+/// The tree details needed to recreate a tree from its data blocks
+///
+/// This is encrypted along with the data blocks in an [`EncryptedTreeEntry`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreeBackupHeader {
+    /// The root of the backed up tree
+    pub root: [u8; 32],
+    /// The tree's secret entropy
+    #[serde(with = "serde_bytes")]
+    pub entropy: Vec<u8>,
+    /// The kind of Merkle tree
+    pub tree_type: TreeType,
+    /// The number of leaves including padding
+    pub total_leaves: u64,
+    /// Whether the tree stored its intermediate layers
+    pub store_layers: bool,
+    /// The chunk size the tree was created with
+    pub chunk_size: Option<u64>,
+    /// The hash function used for the tree's leaves and nodes
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl TreeBackupHeader {
+    /// Get the padding that recreates `total_leaves` from the given number of data blocks
+    pub fn padding(&self, block_count: usize) -> Option<PaddingMode> {
+        match self.total_leaves > block_count as u64 {
+            true => Some( PaddingMode::Size( self.total_leaves ) ),
+            false => None,
+        }
+    }
+}
+
+
+/// An entry struct for an encrypted copy of a tree's data blocks
+///
+/// The header and data blocks are sealed with x25519/xsalsa20poly1305 so only the holder of the
+/// `recipient` key can read them.  Nothing about the tree (not even its root or leaf count) is
+/// public.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct EncryptedTreeEntry {
+    /// The x25519 key that encrypted the backup
+    pub sender: X25519PubKey,
+    /// The x25519 key that can decrypt the backup
+    pub recipient: X25519PubKey,
+    /// The encrypted [`TreeBackupHeader`]
+    pub header: XSalsa20Poly1305EncryptedData,
+    /// The encrypted list of [`LeafDataBlock`]s, in order
+    pub data_blocks: XSalsa20Poly1305EncryptedData,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( EncryptedTreeEntry );
+
+
+
+//
+// Ethereum Export
+//
+fn to_0x(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode( bytes ) )
+}
+
+fn from_0x(text: &str) -> ExternResult<Vec<u8>> {
+    hex::decode( text.trim_start_matches("0x") )
+        .map_err(|err| guest_error!(format!("Invalid hex '{}': {}", text, err )))
+}
+
+fn hash_from_0x(text: &str) -> ExternResult<[u8; 32]> {
+    Ok( MerkleHash::from_slice( &from_0x( text )? )?.0 )
+}
+
+fn ethereum_tree(data_blocks: &[LeafDataBlock]) -> ExternResult<(ethereum::StandardMerkleTree, Vec<[u8; 32]>)> {
+    let leaves = data_blocks.iter()
+        .map(|block| HashAlgorithm::Keccak256.leaf_hash( block ) )
+        .collect::<Result<Vec<[u8; 32]>, verify::VerifyError>>()?;
+    let tree = ethereum::StandardMerkleTree::from_leaves( &leaves )
+        .ok_or(guest_error!(format!("Cannot build an Ethereum tree without leaves")))?;
+
+    Ok( (tree, leaves) )
+}
+
+/// A value in a [`StandardMerkleTreeDump`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StandardMerkleTreeValue {
+    /// The label, `0x` hex msgpack encoded value and `0x` hex salt
+    pub value: Vec<String>,
+    #[serde(rename = "treeIndex")]
+    pub tree_index: u64,
+}
+
+/// A tree in the format of OpenZeppelin's `StandardMerkleTree.dump()`
+///
+/// Load it in JS with `StandardMerkleTree.load( dump )`.  For a tree created with
+/// [`HashAlgorithm::Keccak256`] the dump's root is the tree entry's root.  A SHA-256 tree is
+/// re-hashed into a separate Ethereum tree of its data blocks (without padding leaves), so its dump
+/// has a different root.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StandardMerkleTreeDump {
+    pub format: String,
+    pub tree: Vec<String>,
+    pub values: Vec<StandardMerkleTreeValue>,
+    #[serde(rename = "leafEncoding")]
+    pub leaf_encoding: Vec<String>,
+}
+
+impl StandardMerkleTreeDump {
+    /// Build the Ethereum tree for `data_blocks`
+    pub fn from_data_blocks(data_blocks: &[LeafDataBlock]) -> ExternResult<Self> {
+        let (tree, _) = ethereum_tree( data_blocks )?;
+        let mut values = Vec::new();
+
+        for (block, tree_index) in data_blocks.iter().zip( tree.tree_indices.iter() ) {
+            let [ _, value, salt ] = block.ethereum_values()?;
+
+            values.push( StandardMerkleTreeValue {
+                value: vec![ block.label.clone(), to_0x( &value ), to_0x( &salt ) ],
+                tree_index: *tree_index as u64,
+            });
+        }
+
+        Ok(
+            StandardMerkleTreeDump {
+                format: ethereum::STANDARD_TREE_FORMAT.to_string(),
+                tree: tree.tree.iter().map(|node| to_0x( node ) ).collect(),
+                values,
+                leaf_encoding: ethereum::LEAF_ENCODING.iter().map(|kind| kind.to_string() ).collect(),
+            }
+        )
+    }
+
+    /// The `0x` hex root
+    pub fn root(&self) -> Option<&String> {
+        self.tree.first()
+    }
+}
+
+/// A leaf proof that can be checked with OpenZeppelin's `MerkleProof.verify( proof, root, leaf )`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EthereumLeafProof {
+    /// The revealed data block
+    pub target: LeafDataBlock,
+    /// The `0x` hex leaf hash
+    pub leaf: String,
+    /// The `0x` hex sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+    /// The `0x` hex root
+    pub root: String,
+}
+
+impl EthereumLeafProof {
+    /// Prove the data block with `label` in the Ethereum tree for `data_blocks`
+    pub fn from_data_blocks(data_blocks: &[LeafDataBlock], label: &str) -> ExternResult<Self> {
+        let index = data_blocks.iter()
+            .position(|block| block.label == label )
+            .ok_or(guest_error!(format!("There is no leaf with label '{}'", label )))?;
+        let (tree, leaves) = ethereum_tree( data_blocks )?;
+        let proof = tree.proof( index )
+            .ok_or(guest_error!(format!("Failed to prove leaf {}", index )))?;
+
+        Ok(
+            EthereumLeafProof {
+                target: data_blocks[ index ].clone(),
+                leaf: to_0x( &leaves[ index ] ),
+                proof: proof.iter().map(|hash| to_0x( hash ) ).collect(),
+                root: to_0x( &tree.root() ),
+            }
+        )
+    }
+
+    /// Check that the target matches the leaf and that the proof leads to the root
+    pub fn verify(&self) -> ExternResult<bool> {
+        let leaf = hash_from_0x( &self.leaf )?;
+
+        if HashAlgorithm::Keccak256.leaf_hash( &self.target )? != leaf {
+            return Ok( false );
+        }
+
+        let proof = self.proof.iter()
+            .map(|hash| hash_from_0x( hash ) )
+            .collect::<ExternResult<Vec<[u8; 32]>>>()?;
+
+        Ok( ethereum::verify( &hash_from_0x( &self.root )?, &leaf, &proof ) )
+    }
+}
+
+
+
+//
+// Claim Entry
+//
+/// An entry struct for making a claim about a Merkle tree
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ClaimEntry {
+    /// The name of this claim
+    pub name: String,
+    /// The author making the claim
+    pub author: AgentPubKey,
+    /// A reference to the Merkle tree
+    pub root: MerkleHash,
+
+    // common fields
+    pub metadata: BTreeMap<String, rmpv::Value>,
+}
+common_fields!( ClaimEntry );
+
+
+
+//
+// CSR Input Structs
+//
+/// Create a dummy leaf hash for padding a tree
+///
+/// The hash is derived from the tree's secret entropy so it is indistinguishable from a real leaf
+/// hash and can be recreated by the tree's author.
+pub fn padding_leaf(entropy: &[u8], index: usize) -> ExternResult<[u8; 32]> {
+    Ok( verify::padding_leaf( entropy, index )? )
+}
+
+type OptionalBytes = Option<serde_bytes::ByteBuf>;
+
+/// Input required for creating a tree entry
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateTreeInput {
+    /// A list of data blocks used as the Merkle tree leaves
+    pub leaves: Vec<LeafInput>,
+    /// Entropy used for creating deterministic salts for each leaf
+    pub entropy: OptionalBytes,
+    /// The kind of Merkle tree to create (defaults to [`TreeType::Standard`])
+    pub tree_type: Option<TreeType>,
+    /// Append dummy leaves to hide the number of data blocks (standard trees only)
+    pub padding: Option<PaddingMode>,
+    /// Store the intermediate layers so proofs can be read without rebuilding the tree (standard
+    /// trees only)
+    pub store_layers: Option<bool>,
+    /// The maximum number of leaves per entry before the tree is split into chunks; must be a power
+    /// of two (defaults to [`DEFAULT_CHUNK_SIZE`])
+    pub chunk_size: Option<u64>,
+    /// The hash function for leaves and nodes (defaults to [`HashAlgorithm::Sha256`]);
+    /// [`HashAlgorithm::Keccak256`] builds an OpenZeppelin compatible root (standard trees without
+    /// padding, stored layers or chunks only)
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// Metadata for the tree entry
+    pub metadata: Option<BTreeMap<String, rmpv::Value>>,
+}
+
+/// Input required for getting a leaf proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetLeafProofInput {
+    /// The create action for the target tree entry
+    pub tree_id: ActionHash,
+    /// The label of the target leaf
+    pub label: String,
+    /// The agent the proof is for; when set, the proof is recorded as a [`DisclosureEntry`]
+    pub recipient: Option<AgentPubKey>,
+}
+
+/// Input required for verifying a single leaf proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifyLeafProofInput {
+    /// The Merkle proof hash list
+    pub proof: Vec<MerkleHash>,
+    /// The leaf's index in the Merkle tree
+    pub index: u64,
+    /// The hash of the target leaf
+    pub leaf: MerkleHash,
+    /// The Merkle tree's root hash
+    pub root: MerkleHash,
+    /// The total number of leaves in the Merkle tree
+    pub total_leaves: u64,
+    /// The hash function of the tree (defaults to [`HashAlgorithm::Sha256`])
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+/// Input required for creating an aggregate tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateAggregateTreeInput {
+    /// The sub-trees used as leaves
+    pub trees: Vec<AggregateSource>,
+}
+
+/// Input required for getting a leaf proof through an aggregate tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetAggregateLeafProofInput {
+    /// The create action for the aggregate tree entry
+    pub aggregate_id: ActionHash,
+    /// The create action for the sub-tree entry
+    pub tree_id: ActionHash,
+    /// The label of the target leaf
+    pub label: String,
+    /// The agent the proof is for; when set, the proof is recorded as a [`DisclosureEntry`]
+    pub recipient: Option<AgentPubKey>,
+}
+
+/// Input required for chaining an existing leaf proof through an aggregate tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainLeafProofInput {
+    /// The create action for the aggregate tree entry
+    pub aggregate_id: ActionHash,
+    /// A leaf proof for one of the aggregate's sub-trees
+    pub leaf_proof: LeafProofPayload,
+}
+
+/// Input required for creating a Merkle log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateLogInput {
+    /// Entropy used for creating deterministic salts for each leaf
+    pub entropy: OptionalBytes,
+}
+
+/// Input required for appending leaves to a Merkle log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppendLeavesInput {
+    /// The create action for the target log entry
+    pub log_id: ActionHash,
+    /// A list of data blocks to append
+    pub leaves: Vec<LeafInput>,
+}
+
+/// Input required for getting a consistency proof between two sizes of a Merkle log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetConsistencyProofInput {
+    /// The create action for the target log entry
+    pub log_id: ActionHash,
+    /// The earlier log size
+    pub old_size: u64,
+    /// The later log size
+    pub new_size: u64,
+}
+
+/// Input required for getting a proof of a Merkle log leaf
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetLogLeafProofInput {
+    /// The create action for the target log entry
+    pub log_id: ActionHash,
+    /// The index of the target leaf
+    pub index: u64,
+    /// The log size to prove against (defaults to the current size)
+    pub size: Option<u64>,
+}
+
+/// Input required for creating a Merkle Mountain Range
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateMmrInput {
+    /// Entropy used for creating deterministic salts for each leaf
+    pub entropy: OptionalBytes,
+}
+
+/// Input required for appending a leaf to a Merkle Mountain Range
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppendMmrLeafInput {
+    /// The create action for the target MMR entry
+    pub mmr_id: ActionHash,
+    /// The leaf to append
+    pub leaf: LeafInput,
+}
+
+/// Input required for getting a proof of a Merkle Mountain Range leaf
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetMmrProofInput {
+    /// The create action for the target MMR entry
+    pub mmr_id: ActionHash,
+    /// The index of the target leaf
+    pub index: u64,
+}
+
+/// Input required for creating an anchor
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateAnchorInput {
+    /// The roots to anchor
+    pub roots: Vec<[u8; 32]>,
+}
+
+/// Input required for asking a witness to anchor some roots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestAnchorInput {
+    /// The agent that will create the anchor
+    pub witness: AgentPubKey,
+    /// The secret from the witness's `allow_anchor_requests`
+    pub cap_secret: CapSecret,
+    /// The roots to anchor
+    pub roots: Vec<[u8; 32]>,
+}
+
+/// Input required for getting an anchor proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetAnchorProofInput {
+    /// The anchored root
+    pub root: [u8; 32],
+    /// A specific anchor to prove against (defaults to the earliest anchor of `root`)
+    pub anchor_id: Option<ActionHash>,
+}
+
+/// Input required for attesting a holder's root
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttestRootInput {
+    /// The agent that holds the tree
+    pub holder: AgentPubKey,
+    /// The root to attest
+    pub root: [u8; 32],
+}
+
+/// Input required for verifying a leaf proof that is attested by an issuer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifyAttestedLeafProofInput {
+    /// The leaf proof to verify
+    pub leaf_proof: LeafProofPayload,
+    /// The issuer that must have attested the proof's root
+    pub issuer: AgentPubKey,
+}
+
+/// Input required for asking an issuer to issue a credential
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestCredentialInput {
+    /// The agent that will issue the credential
+    pub issuer: AgentPubKey,
+    /// The leaves to be issued
+    pub leaves: Vec<LeafInput>,
+}
+
+/// Input required for asking a holder for proofs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestProofInput {
+    /// The agent holding the tree
+    pub holder: AgentPubKey,
+    /// The labels to prove
+    pub labels: Vec<String>,
+    /// The root that the proofs must be for (any tree if `None`)
+    pub root: Option<[u8; 32]>,
+}
+
+/// Input required for approving a proof request
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApproveProofRequestInput {
+    /// The create action of the proof request
+    pub request_id: ActionHash,
+    /// The tree to present the proofs from
+    pub tree_id: ActionHash,
+}
+
+/// Input required for granting a proof delegation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GrantProofDelegationInput {
+    /// The agent allowed to request proofs
+    pub delegate: AgentPubKey,
+    /// The tree that proofs can be generated from
+    pub tree_id: ActionHash,
+    /// The labels that can be proven
+    pub labels: Vec<String>,
+    /// When the delegation stops being honoured (never if `None`)
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Input required for requesting a proof from another agent's tree as their delegate
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DelegatedProofInput {
+    /// The agent holding the tree
+    pub author: AgentPubKey,
+    /// The secret returned when the delegation was granted
+    pub cap_secret: CapSecret,
+    /// The create action for the target tree entry
+    pub tree_id: ActionHash,
+    /// The label of the target leaf
+    pub label: String,
+}
+
+/// Input required for backing up a tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupTreeInput {
+    /// The create action for the tree entry
+    pub tree_id: ActionHash,
+    /// The x25519 key that can decrypt the backup
+    ///
+    /// This must be a key from another device's keystore (eg. its `create_recovery_key`) so that
+    /// the backup can still be decrypted after losing this device.
+    pub recovery_key: X25519PubKey,
+}
+
+/// Input required for getting a leaf proof from an encrypted backup
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetBackupLeafProofInput {
+    /// The create action for the encrypted tree entry
+    pub backup_id: ActionHash,
+    /// The label of the target leaf
+    pub label: String,
+}
+
+/// Input required for filtering the disclosure history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetDisclosureHistoryInput {
+    /// Only include disclosures from this tree
+    pub tree_id: Option<ActionHash>,
+    /// Only include disclosures to this agent
+    pub recipient: Option<AgentPubKey>,
+}
+
+/// Input required for presenting a subset of an SD-JWT's disclosures
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentSdJwtInput {
+    /// The SD-JWT returned by `issue_sd_jwt`
+    pub sd_jwt: sd_jwt::SdJwt,
+    /// The labels to reveal
+    pub labels: Vec<String>,
+}
+
+/// Input required for encoding a leaf proof for a QR code
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncodeCompactProofInput {
+    /// The leaf proof to encode
+    pub proof: LeafProofPayload,
+    /// Truncate the root to this many bytes (insecure; see [`compact`])
+    pub root_prefix: Option<usize>,
+    /// Fail if the encoding is larger than this many bytes
+    pub budget: Option<usize>,
+}
+
+/// Input required for decoding a leaf proof from a QR code
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecodeCompactProofInput {
+    /// The compact proof bytes
+    pub bytes: serde_bytes::ByteBuf,
+    /// The root the proof must be for; required when the encoded root is truncated
+    pub expected_root: Option<[u8; 32]>,
+}
+
+/// Input required for verifying a sparse Merkle proof
+///
+/// A `leaf` of `None` verifies that the tree has no leaf for `label`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifySparseProofInput {
+    /// The compressed sparse Merkle proof
+    pub proof: SparseMerkleProof,
+    /// The label that the proof is for
+    pub label: String,
+    /// The sha256 hash of the target leaf
+    pub leaf: Option<[u8; 32]>,
+    /// The sparse Merkle tree's root hash
+    pub root: [u8; 32],
+}
+
+impl VerifySparseProofInput {
+    /// Verify the inclusion (or exclusion) of this input's label
+    pub fn verify(&self) -> bool {
+        let key = sparse_merkle_tree::label_key( &self.label );
+
+        match &self.leaf {
+            Some(leaf) => self.proof.verify_inclusion( &self.root, &key, leaf ),
+            None => self.proof.verify_exclusion( &self.root, &key ),
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::{ sha256, Serialize, PaddingMode, LeafInput, LeafProofPayload, TreeEntry, TreeType, MerkleHash, BTreeMap, HashAlgorithm, AttestationEntry, AgentPubKey, algorithms };
+    use rs_merkle::MerkleTree;
+    use crate::TreeBundle;
+    use serde_json::json;
+
+    #[test]
+    fn test_sha256() {
+        #[derive(Debug, Serialize)]
+        pub struct Data( Option<u8> );
+
+        assert_eq!( sha256( &Data(None) ).unwrap(), [
+            228, 255, 94, 125, 122, 127, 8, 233,
+            128, 10, 62, 37, 203, 119, 69, 51,
+            203, 32, 4, 13, 243, 11, 107, 161,
+            15, 149, 111, 154, 205, 14, 179, 247
+        ] );
+    }
+
+    #[test]
+    fn test_attestation_message() {
+        let holder = AgentPubKey::from_raw_32( vec![ 9; 32 ] );
+        let content = AttestationEntry::signed_content( &holder, &[ 3; 32 ] );
+
+        assert!( content.starts_with( b"merklicious:attestation:v1" ) );
+        assert_eq!( &content[ 26..65 ], holder.get_raw_39() );
+        assert_eq!( &content[ 65.. ], &[ 3; 32 ] );
+    }
+
+    const VECTORS_PATH: &str = concat!( env!("CARGO_MANIFEST_DIR"), "/../tests/vectors/merklicious_v1.json" );
+
+    fn hex_list(hashes: &[[u8; 32]]) -> Vec<String> {
+        hashes.iter().map( hex::encode ).collect()
+    }
+
+    /// Rebuild a vector case from its inputs (`name`, `entropy`, `leaves` and `padding`)
+    fn vector_case(case: &serde_json::Value) -> serde_json::Value {
+        let inputs = case["leaves"].as_array().unwrap();
+        let leaves : Vec<LeafInput> = inputs.iter()
+            .map(|leaf| LeafInput {
+                label: leaf["label"].as_str().unwrap().to_string(),
+                value: serde_json::from_value( leaf["value"].clone() ).unwrap(),
+            })
+            .collect();
+        let padding : Option<PaddingMode> = serde_json::from_value( case["padding"].clone() ).unwrap();
+        let entropy = hex::decode( case["entropy"].as_str().unwrap() ).unwrap();
+        let bundle = TreeBundle::build( leaves, entropy, TreeType::Standard, HashAlgorithm::Sha256, padding ).unwrap();
+        let tree = MerkleTree::<algorithms::Sha256>::from_leaves( &bundle.leaves );
+        let block_count = bundle.data_blocks.len();
+
+        let mut output = json!({
+            "name": case["name"],
+            "entropy": case["entropy"],
+        });
+
+        if !case["padding"].is_null() {
+            output["padding"] = case["padding"].clone();
+        }
+
+        output["leaves"] = bundle.data_blocks.iter().zip( inputs )
+            .map(|(block, input)| {
+                // `LeafDataBlock::hash` must agree with the generic serializer
+                assert_eq!( block.hash().unwrap(), sha256( &block ).unwrap() );
+
+                json!({
+                    "label": block.label,
+                    "value": input["value"],
+                    "salt": hex::encode( &block.salt ),
+                    "leaf": hex::encode( block.hash().unwrap() ),
+                })
+            })
+            .collect();
+
+        if !case["padding"].is_null() {
+            output["padding_leaves"] = json!( hex_list( &bundle.leaves[ block_count.. ] ) );
+        }
+
+        output["root"] = json!( hex::encode( bundle.root ) );
+        output["proofs"] = (0..block_count)
+            .map(|index| json!( hex_list( tree.proof( &[ index ] ).proof_hashes() ) ) )
+            .collect();
+
+        output
+    }
+
+    #[test]
+    fn test_vectors() {
+        // Run with MERKLICIOUS_UPDATE_VECTORS=1 to regenerate the file from each case's inputs.  Any
+        // difference is a breaking change for existing trees and other-language verifiers.
+        let mut vectors : serde_json::Value = serde_json::from_str( &std::fs::read_to_string( VECTORS_PATH ).unwrap() ).unwrap();
+        let expected : Vec<serde_json::Value> = vectors["cases"].as_array().unwrap().iter()
+            .map( vector_case )
+            .collect();
+
+        if std::env::var("MERKLICIOUS_UPDATE_VECTORS").is_ok() {
+            vectors["cases"] = json!( expected );
+            std::fs::write( VECTORS_PATH, serde_json::to_string_pretty( &vectors ).unwrap() + "\n" ).unwrap();
+            return;
+        }
+
+        assert_eq!( vectors["version"], 1 );
+
+        for (case, expected) in vectors["cases"].as_array().unwrap().iter().zip( expected ) {
+            assert_eq!( case, &expected, "vector '{}'", case["name"] );
+        }
+    }
+
+    #[test]
+    fn test_compact_truncated_root() {
+        let leaves = vec![ "name", "age", "city" ].into_iter()
+            .map(|label| LeafInput {
+                label: label.to_string(),
+                value: rmpv::Value::from( label ),
+            })
+            .collect();
+        let bundle = TreeBundle::build( leaves, b"entropy".to_vec(), TreeType::Standard, HashAlgorithm::Sha256, None ).unwrap();
+        let proof = bundle.leaf_proof( "age" ).unwrap();
+        let truncated = proof.to_compact( Some( 8 ), None ).unwrap();
+
+        // A truncated root is never rehydrated without a known root to check against
+        assert!( LeafProofPayload::from_compact( &truncated, None ).is_err() );
+        assert!( LeafProofPayload::from_compact( &truncated, Some( &[ 0; 32 ] ) ).is_err() );
+
+        let decoded = LeafProofPayload::from_compact( &truncated, Some( &bundle.root ) ).unwrap();
+        assert_eq!( decoded.root, proof.root );
+        assert!( decoded.verify().unwrap() );
+
+        let full = proof.to_compact( None, None ).unwrap();
+        assert!( LeafProofPayload::from_compact( &full, None ).is_ok() );
+        assert!( LeafProofPayload::from_compact( &full, Some( &[ 0; 32 ] ) ).is_err() );
+    }
+
+    #[test]
+    fn test_effective_chunk_size() {
+        let mut tree = TreeEntry {
+            data_blocks: None,
+            leaves: vec![ MerkleHash::from( [ 0; 32 ] ); 1500 ],
+            entropy: Vec::new(),
+            root: MerkleHash::from( [ 0; 32 ] ),
+            tree_type: TreeType::Standard,
+            layers: None,
+            chunks: Vec::new(),
+            chunk_size: Some( 4096 ),
+            hash_algorithm: HashAlgorithm::Sha256,
+            metadata: BTreeMap::new(),
+        };
+
+        assert_eq!( tree.effective_chunk_size(), 4096 );
+
+        // An unchunked tree stored without its chunk size must still fit in one chunk
+        tree.chunk_size = None;
+        assert_eq!( tree.effective_chunk_size(), 2048 );
+    }
+}
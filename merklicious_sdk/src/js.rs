@@ -0,0 +1,89 @@
+//! JavaScript bindings for the verifier
+//!
+//! Built with `--no-default-features --features js` (eg. `wasm-pack build`) so a browser can check
+//! leaf proofs without calling the conductor.  Hashes are passed as `Uint8Array`s and data block
+//! values as plain JavaScript values, the same as the zome functions they replace.
+//!
+//! Functions that take a `hashAlgorithm` accept `"Sha256"` (the default when it is left out) or
+//! `"Keccak256"`, the same as a tree entry's `hash_algorithm`.
+
+use wasm_bindgen::prelude::*;
+use crate::{ transport, verify, HashAlgorithm, MerkleHash };
+
+
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value( value )
+        .map_err(|err| JsError::new( &err.to_string() ) )
+}
+
+fn hash_from_slice(bytes: &[u8]) -> Result<[u8; 32], JsError> {
+    Ok( MerkleHash::from_slice( bytes )?.0 )
+}
+
+fn algorithm_from_js(value: JsValue) -> Result<HashAlgorithm, JsError> {
+    Ok( from_js::<Option<HashAlgorithm>>( value )?.unwrap_or_default() )
+}
+
+
+/// Verify a leaf proof payload (the output of `get_leaf_proof`) or its transport string
+#[wasm_bindgen(js_name = verifyPayload)]
+pub fn verify_payload(payload: JsValue) -> Result<bool, JsError> {
+    let proof : verify::LeafProof = match payload.as_string() {
+        Some(text) => transport::decode( &text )?,
+        None => from_js( payload )?,
+    };
+
+    Ok( proof.verify()? )
+}
+
+
+/// Get the leaf hash of a data block (same as the `hash_data_block` zome function for SHA-256
+/// trees)
+#[wasm_bindgen(js_name = hashLeaf)]
+pub fn hash_leaf(label: &str, value: JsValue, salt: &[u8], hash_algorithm: JsValue) -> Result<Vec<u8>, JsError> {
+    let value : rmpv::Value = from_js( value )?;
+
+    Ok( algorithm_from_js( hash_algorithm )?.hash_leaf( label, &value, salt )?.to_vec() )
+}
+
+
+/// Derive the salt of the leaf at `index` from a tree's entropy
+#[wasm_bindgen(js_name = deriveSalt)]
+pub fn derive_salt(entropy: &[u8], index: u32) -> Result<Vec<u8>, JsError> {
+    Ok( verify::derive_salt( entropy, index as usize )? )
+}
+
+
+/// Get the root of a standard tree from its leaf hashes
+#[wasm_bindgen(js_name = calculateRoot)]
+pub fn calculate_root(leaves: JsValue, hash_algorithm: JsValue) -> Result<Option<Vec<u8>>, JsError> {
+    let leaves : Vec<MerkleHash> = from_js( leaves )?;
+
+    Ok( algorithm_from_js( hash_algorithm )?.root( &MerkleHash::to_raw_vec( &leaves ) ).map(|root| root.to_vec() ) )
+}
+
+
+/// Get the root that `proof` leads to from `leaf` at `index` of a SHA-256 tree
+#[wasm_bindgen(js_name = proofRoot)]
+pub fn proof_root(index: u32, leaf: &[u8], proof: JsValue, total_leaves: u32) -> Result<Option<Vec<u8>>, JsError> {
+    let proof : Vec<MerkleHash> = from_js( proof )?;
+
+    Ok(
+        verify::proof_root( index as u64, &hash_from_slice( leaf )?, &MerkleHash::to_raw_vec( &proof ), total_leaves as u64 )
+            .map(|root| root.to_vec() )
+    )
+}
+
+
+/// Check that `proof` leads from `leaf` at `index` to `root` (same as the `verify_leaf_proof` zome
+/// function)
+#[wasm_bindgen(js_name = verifyLeafProof)]
+pub fn verify_leaf_proof(root: &[u8], index: u32, leaf: &[u8], proof: JsValue, total_leaves: u32, hash_algorithm: JsValue) -> Result<bool, JsError> {
+    let proof : Vec<MerkleHash> = from_js( proof )?;
+
+    Ok(
+        algorithm_from_js( hash_algorithm )?.verify_proof(
+            &hash_from_slice( root )?, index as u64, &hash_from_slice( leaf )?, &MerkleHash::to_raw_vec( &proof ), total_leaves as u64
+        )
+    )
+}
@@ -0,0 +1,173 @@
+//! A 32 byte Merkle hash (root, leaf or proof hash)
+//!
+//! [`MerkleHash`] serializes exactly like `[u8; 32]` so it can replace raw arrays without changing
+//! the wire format.  It displays as hex and can also be converted to and from base64url.
+
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::convert::TryFrom;
+use serde::{ Serialize, Deserialize };
+use crate::encoding;
+
+
+/// Errors from parsing a [`MerkleHash`]
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum MerkleHashError {
+    #[error("Invalid hex for Merkle hash: {0}")]
+    InvalidHex(String),
+    #[error("Invalid base64url for Merkle hash")]
+    InvalidBase64,
+    #[error("Merkle hash must be 32 bytes; found {0}")]
+    InvalidLength(usize),
+}
+
+
+/// A 32 byte Merkle hash
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MerkleHash( pub [u8; 32] );
+
+impl MerkleHash {
+    /// The raw bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Get a hash from a byte slice that must be exactly 32 bytes
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, MerkleHashError> {
+        <[u8; 32]>::try_from( bytes )
+            .map( MerkleHash )
+            .map_err(|_| MerkleHashError::InvalidLength( bytes.len() ) )
+    }
+
+    /// Lowercase hex
+    pub fn to_hex(&self) -> String {
+        hex::encode( self.0 )
+    }
+
+    /// Parse 64 hex characters
+    pub fn from_hex(text: &str) -> Result<Self, MerkleHashError> {
+        let bytes = hex::decode( text )
+            .map_err(|err| MerkleHashError::InvalidHex( err.to_string() ) )?;
+
+        Self::from_slice( &bytes )
+    }
+
+    /// URL safe base64 without padding
+    pub fn to_base64(&self) -> String {
+        encoding::base64url_encode( &self.0 )
+    }
+
+    /// Parse URL safe base64 (padding is optional)
+    pub fn from_base64(text: &str) -> Result<Self, MerkleHashError> {
+        let bytes = encoding::base64url_decode( text )
+            .ok_or( MerkleHashError::InvalidBase64 )?;
+
+        Self::from_slice( &bytes )
+    }
+
+    /// Convert a list of hashes to raw arrays (eg. for [`rs_merkle`])
+    pub fn to_raw_vec(hashes: &[MerkleHash]) -> Vec<[u8; 32]> {
+        hashes.iter().map(|hash| hash.0 ).collect()
+    }
+
+    /// Convert a list of raw arrays to hashes
+    pub fn from_raw_vec(hashes: Vec<[u8; 32]>) -> Vec<MerkleHash> {
+        hashes.into_iter().map( MerkleHash ).collect()
+    }
+}
+
+impl fmt::Display for MerkleHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!( f, "{}", self.to_hex() )
+    }
+}
+
+impl fmt::Debug for MerkleHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!( f, "MerkleHash({})", self.to_hex() )
+    }
+}
+
+impl FromStr for MerkleHash {
+    type Err = MerkleHashError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_hex( text )
+    }
+}
+
+impl Deref for MerkleHash {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for MerkleHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for MerkleHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        MerkleHash( bytes )
+    }
+}
+
+impl From<MerkleHash> for [u8; 32] {
+    fn from(hash: MerkleHash) -> Self {
+        hash.0
+    }
+}
+
+impl PartialEq<[u8; 32]> for MerkleHash {
+    fn eq(&self, other: &[u8; 32]) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<MerkleHash> for [u8; 32] {
+    fn eq(&self, other: &MerkleHash) -> bool {
+        self == &other.0
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "6bf2bb30219283414ae2b1fa507067f94d86c3f99b8c078edf73692b7c8b76a3";
+
+    #[test]
+    fn test_text_round_trip() {
+        let hash : MerkleHash = HEX.parse().unwrap();
+
+        assert_eq!( hash.to_string(), HEX );
+        assert_eq!( hash.to_base64(), "a_K7MCGSg0FK4rH6UHBn-U2Gw_mbjAeO33NpK3yLdqM" );
+        assert_eq!( MerkleHash::from_base64( &hash.to_base64() ).unwrap(), hash );
+        assert_eq!( format!("{:?}", hash ), format!("MerkleHash({})", HEX ) );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!( MerkleHash::from_hex( "abcd" ), Err(MerkleHashError::InvalidLength(2)) );
+        assert!( matches!( "xyz".parse::<MerkleHash>(), Err(MerkleHashError::InvalidHex(_)) ) );
+        assert_eq!( MerkleHash::from_base64( "a+b" ), Err(MerkleHashError::InvalidBase64) );
+    }
+
+    #[test]
+    fn test_wire_compatible() {
+        let hash = MerkleHash( [ 200; 32 ] );
+
+        assert_eq!( rmp_serde::to_vec( &hash ).unwrap(), rmp_serde::to_vec( &[ 200u8; 32 ] ).unwrap() );
+        assert_eq!( rmp_serde::to_vec_named( &vec![ hash ] ).unwrap(), rmp_serde::to_vec_named( &vec![ [ 200u8; 32 ] ] ).unwrap() );
+        assert_eq!( rmp_serde::from_slice::<MerkleHash>( &rmp_serde::to_vec( &[ 200u8; 32 ] ).unwrap() ).unwrap(), hash );
+        assert_eq!( serde_json::to_string( &hash ).unwrap(), serde_json::to_string( &[ 200u8; 32 ] ).unwrap() );
+    }
+}
@@ -0,0 +1,149 @@
+//! Precomputed layers for a standard ([`rs_merkle`] compatible) Merkle tree.
+//!
+//! Storing the intermediate layers of a tree lets a proof be read directly out of the layers
+//! instead of rebuilding the whole tree.  The hashing matches [`rs_merkle::MerkleTree`] with the
+//! `Sha256` algorithm: a parent is the hash of its concatenated children and the last node of an
+//! uneven layer is promoted to the next layer unchanged.
+use sha2::{ Sha256, Digest };
+
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update( left );
+    hasher.update( right );
+    <[u8; 32]>::from( hasher.finalize() )
+}
+
+/// Calculate the layers above the given leaves
+///
+/// The returned list starts with the parents of `leaves` and ends with the layer containing only
+/// the root.  It is empty when there are fewer than 2 leaves.
+pub fn build_layers(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut layers : Vec<Vec<[u8; 32]>> = Vec::new();
+    let mut current = leaves.to_vec();
+
+    while current.len() > 1 {
+        current = current.chunks( 2 )
+            .map(|pair| match pair {
+                [left, right] => hash_pair( left, right ),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        layers.push( current.clone() );
+    }
+
+    layers
+}
+
+/// Get the root from a set of leaves and the layers built by [`build_layers`]
+pub fn root_from_layers(leaves: &[[u8; 32]], layers: &[Vec<[u8; 32]>]) -> Option<[u8; 32]> {
+    match layers.last() {
+        Some(layer) => layer.first().cloned(),
+        None => leaves.first().cloned(),
+    }
+}
+
+/// Read the proof hashes for a single leaf out of a tree's leaves and layers
+///
+/// The result is identical to `MerkleTree::proof( &[index] ).proof_hashes()`.  Returns `None` if
+/// the index is out of range.
+pub fn proof_from_layers(leaves: &[[u8; 32]], layers: &[Vec<[u8; 32]>], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut position = index;
+
+    for layer in std::iter::once( leaves ).chain( layers.iter().map(|layer| layer.as_slice() ) ) {
+        if layer.len() == 1 {
+            break;
+        }
+
+        if let Some(sibling) = layer.get( position ^ 1 ) {
+            proof.push( *sibling );
+        }
+        position /= 2;
+    }
+
+    Some( proof )
+}
+
+/// Build the proof for a leaf of a tree whose leaves are split into chunks
+///
+/// Every chunk except the last must contain the same power-of-two number of leaves so that each
+/// chunk root is a node of the full tree.  The result is identical to the proof from the full
+/// tree.
+pub fn chunked_proof(chunk_leaves: &[[u8; 32]], leaf_index: usize, chunk_roots: &[[u8; 32]], chunk_index: usize) -> Option<Vec<[u8; 32]>> {
+    let mut proof = proof_from_layers( chunk_leaves, &build_layers( chunk_leaves ), leaf_index )?;
+
+    proof.extend( proof_from_layers( chunk_roots, &build_layers( chunk_roots ), chunk_index )? );
+
+    Some( proof )
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rs_merkle::{ MerkleTree, algorithms };
+
+    fn leaves(count: u8) -> Vec<[u8; 32]> {
+        (0..count).map(|n| [n; 32] ).collect()
+    }
+
+    #[test]
+    fn test_matches_rs_merkle() {
+        for count in 1..=33 {
+            let leaves = leaves( count );
+            let layers = build_layers( &leaves );
+            let tree = MerkleTree::<algorithms::Sha256>::from_leaves( &leaves );
+
+            assert_eq!( root_from_layers( &leaves, &layers ), tree.root() );
+
+            for index in 0..leaves.len() {
+                assert_eq!(
+                    proof_from_layers( &leaves, &layers, index ).unwrap(),
+                    tree.proof( &[index] ).proof_hashes().to_vec(),
+                    "proof mismatch for leaf {} of {}", index, count
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunked_proof() {
+        for chunk_size in [1, 2, 4, 8] {
+            for count in 1..=40 {
+                let leaves = leaves( count );
+                let tree = MerkleTree::<algorithms::Sha256>::from_leaves( &leaves );
+                let chunk_roots : Vec<[u8; 32]> = leaves.chunks( chunk_size )
+                    .map(|chunk| root_from_layers( chunk, &build_layers( chunk ) ).unwrap() )
+                    .collect();
+
+                assert_eq!( root_from_layers( &chunk_roots, &build_layers( &chunk_roots ) ), tree.root() );
+
+                for index in 0..leaves.len() {
+                    let chunk_index = index / chunk_size;
+                    let chunk = leaves.chunks( chunk_size ).nth( chunk_index ).unwrap();
+
+                    assert_eq!(
+                        chunked_proof( chunk, index % chunk_size, &chunk_roots, chunk_index ).unwrap(),
+                        tree.proof( &[index] ).proof_hashes().to_vec(),
+                        "proof mismatch for leaf {} of {} (chunk size {})", index, count, chunk_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let leaves = leaves( 5 );
+        let layers = build_layers( &leaves );
+
+        assert_eq!( proof_from_layers( &leaves, &layers, 5 ), None );
+    }
+}
@@ -0,0 +1,286 @@
+//! An append-only Merkle log following [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962) (also
+//! described in RFC 9162 section 2.1).
+//!
+//! Leaves and interior nodes are domain separated (`0x00` and `0x01` prefixes) and a tree of `n`
+//! leaves is split at the largest power of two smaller than `n`.  This is what makes a
+//! consistency proof possible: the tree for an earlier size is always made of complete subtrees
+//! of the tree for a later size.
+use sha2::{ Sha256, Digest };
+
+
+/// Hash a leaf's data with the RFC 6962 leaf prefix
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update( [0x00] );
+    hasher.update( data );
+    <[u8; 32]>::from( hasher.finalize() )
+}
+
+/// Hash two child nodes with the RFC 6962 interior node prefix
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update( [0x01] );
+    hasher.update( left );
+    hasher.update( right );
+    <[u8; 32]>::from( hasher.finalize() )
+}
+
+/// The largest power of two that is smaller than `n` (`n` must be greater than 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Calculate the Merkle Tree Hash (`MTH`) of a list of leaf hashes
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => <[u8; 32]>::from( Sha256::digest( [] ) ),
+        1 => leaves[0],
+        n => {
+            let k = split_point( n );
+            node_hash( &root( &leaves[ ..k ] ), &root( &leaves[ k.. ] ) )
+        },
+    }
+}
+
+/// Create the audit path (`PATH`) for the leaf at `index`
+///
+/// Returns `None` if the index is out of range.
+pub fn inclusion_proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    fn path(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+        if leaves.len() <= 1 {
+            return Vec::new();
+        }
+
+        let k = split_point( leaves.len() );
+        let (left, right) = leaves.split_at( k );
+
+        match index < k {
+            true => {
+                let mut proof = path( left, index );
+                proof.push( root( right ) );
+                proof
+            },
+            false => {
+                let mut proof = path( right, index - k );
+                proof.push( root( left ) );
+                proof
+            },
+        }
+    }
+
+    Some( path( leaves, index ) )
+}
+
+/// Create the consistency proof (`PROOF`) between the first `old_size` leaves and all `leaves`
+///
+/// Returns `None` unless `0 < old_size <= leaves.len()`.
+pub fn consistency_proof(leaves: &[[u8; 32]], old_size: usize) -> Option<Vec<[u8; 32]>> {
+    if old_size == 0 || old_size > leaves.len() {
+        return None;
+    }
+
+    fn subproof(leaves: &[[u8; 32]], m: usize, complete: bool) -> Vec<[u8; 32]> {
+        let n = leaves.len();
+
+        if m == n {
+            return match complete {
+                true => Vec::new(),
+                false => vec![ root( leaves ) ],
+            };
+        }
+
+        let k = split_point( n );
+        let (left, right) = leaves.split_at( k );
+
+        match m <= k {
+            true => {
+                let mut proof = subproof( left, m, complete );
+                proof.push( root( right ) );
+                proof
+            },
+            false => {
+                let mut proof = subproof( right, m - k, false );
+                proof.push( root( left ) );
+                proof
+            },
+        }
+    }
+
+    Some( subproof( leaves, old_size, true ) )
+}
+
+/// Verify an audit path for `leaf` at `index` in a tree of `size` leaves
+pub fn verify_inclusion(index: u64, size: u64, leaf: &[u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    if index >= size {
+        return false;
+    }
+
+    let mut fn_ = index;
+    let mut sn = size - 1;
+    let mut node = *leaf;
+
+    for sibling in proof {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            node = node_hash( sibling, &node );
+
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            node = node_hash( &node, sibling );
+        }
+
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && node == *root
+}
+
+/// Verify that the tree of `new_size` leaves with `new_root` is an extension of the tree of
+/// `old_size` leaves with `old_root`
+pub fn verify_consistency(
+    old_size: u64,
+    new_size: u64,
+    old_root: &[u8; 32],
+    new_root: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> bool {
+    if old_size > new_size {
+        return false;
+    }
+
+    if old_size == new_size {
+        return proof.is_empty() && old_root == new_root;
+    }
+
+    // Every tree is an extension of the empty tree
+    if old_size == 0 {
+        return proof.is_empty();
+    }
+
+    if proof.is_empty() {
+        return false;
+    }
+
+    let mut path = proof.to_vec();
+
+    if old_size.is_power_of_two() {
+        path.insert( 0, *old_root );
+    }
+
+    let mut fn_ = old_size - 1;
+    let mut sn = new_size - 1;
+
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    let mut fr = path[0];
+    let mut sr = path[0];
+
+    for node in &path[1..] {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = node_hash( node, &fr );
+            sr = node_hash( node, &sr );
+
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            sr = node_hash( &sr, node );
+        }
+
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    fr == *old_root && sr == *new_root && sn == 0
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        (0..count).map(|n| leaf_hash( &n.to_le_bytes() ) ).collect()
+    }
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(
+            hex::encode( root( &[] ) ),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        for size in 1..=20 {
+            let leaves = leaves( size );
+            let root = root( &leaves );
+
+            for index in 0..size {
+                let proof = inclusion_proof( &leaves, index ).unwrap();
+
+                assert!( verify_inclusion( index as u64, size as u64, &leaves[index], &proof, &root ) );
+                assert!( !verify_inclusion( ((index + 1) % size) as u64, size as u64, &leaves[index], &proof, &root ) || size == 1 );
+            }
+
+            assert_eq!( inclusion_proof( &leaves, size ), None );
+        }
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        for new_size in 1..=20 {
+            let leaves = leaves( new_size );
+            let new_root = root( &leaves );
+
+            for old_size in 1..=new_size {
+                let old_root = root( &leaves[ ..old_size ] );
+                let proof = consistency_proof( &leaves, old_size ).unwrap();
+
+                assert!(
+                    verify_consistency( old_size as u64, new_size as u64, &old_root, &new_root, &proof ),
+                    "consistency {} -> {} failed", old_size, new_size
+                );
+                assert!( !verify_consistency( old_size as u64, new_size as u64, &new_root, &old_root, &proof ) || old_size == new_size );
+                assert!( !verify_consistency( old_size as u64, new_size as u64, &leaf_hash( b"other" ), &new_root, &proof ) );
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_rejects_rewritten_history() {
+        let leaves = leaves( 8 );
+        let mut rewritten = leaves.clone();
+        rewritten[2] = leaf_hash( b"rewritten" );
+
+        let old_root = root( &leaves[ ..5 ] );
+        let proof = consistency_proof( &rewritten, 5 ).unwrap();
+
+        assert!( !verify_consistency( 5, 8, &old_root, &root( &rewritten ), &proof ) );
+    }
+}
@@ -0,0 +1,262 @@
+//! A Merkle Mountain Range (MMR) accumulator.
+//!
+//! An MMR is a list of perfect binary trees ("mountains") whose sizes follow the binary
+//! representation of the leaf count.  Appending a leaf only merges the mountains on the right, so
+//! the whole structure is described by its peaks and a node never changes once it is created.
+//!
+//! Nodes are addressed by `(height, index)` where index counts the nodes of that height from the
+//! left.  The node `(height, index)` covers leaves `index * 2^height ..(index + 1) * 2^height` and is
+//! created by the append of the last leaf it covers (see [`created_by`]).  Leaves and interior nodes
+//! use the same domain separated hashing as [`crate::merkle_log`].
+use crate::merkle_log::node_hash;
+use sha2::{ Sha256, Digest };
+
+
+/// The heights of the mountains for an MMR of `size` leaves, from left to right
+pub fn mountain_heights(size: u64) -> Vec<u32> {
+    (0..64).rev()
+        .filter(|height| size & (1 << height) != 0 )
+        .collect()
+}
+
+/// Get the index of the leaf whose append creates the node `(height, index)`
+pub fn created_by(height: u32, index: u64) -> u64 {
+    ((index + 1) << height) - 1
+}
+
+/// Append a leaf to an MMR of `size` leaves with `peaks`
+///
+/// Returns the nodes created by this append (starting with `leaf` at height 0) and the new peaks.
+pub fn append(peaks: &[[u8; 32]], size: u64, leaf: [u8; 32]) -> (Vec<[u8; 32]>, Vec<[u8; 32]>) {
+    let mut peaks = peaks.to_vec();
+    let mut nodes = vec![ leaf ];
+    let mut node = leaf;
+    let mut remaining = size;
+
+    // Every trailing 1 bit of the size is a mountain with the same height as the new node
+    while remaining & 1 == 1 {
+        match peaks.pop() {
+            Some(left) => node = node_hash( &left, &node ),
+            None => break,
+        }
+        nodes.push( node );
+        remaining >>= 1;
+    }
+
+    peaks.push( node );
+
+    (nodes, peaks)
+}
+
+/// Bag the peaks into a single root, folding from the right
+pub fn bag_peaks(peaks: &[[u8; 32]]) -> [u8; 32] {
+    match peaks.split_last() {
+        None => <[u8; 32]>::from( Sha256::digest( [] ) ),
+        Some((last, rest)) => rest.iter().rev()
+            .fold( *last, |root, peak| node_hash( peak, &root ) ),
+    }
+}
+
+/// Find the mountain containing the leaf at `index` in an MMR of `size` leaves
+///
+/// Returns the mountain's position in the peaks list, its height and the index of its first leaf.
+pub fn find_mountain(index: u64, size: u64) -> Option<(usize, u32, u64)> {
+    let mut offset = 0;
+
+    for (position, height) in mountain_heights( size ).into_iter().enumerate() {
+        let width = 1 << height;
+
+        if index < offset + width {
+            return Some( (position, height, offset) );
+        }
+        offset += width;
+    }
+
+    None
+}
+
+/// The `(height, index)` address of every sibling on the path from leaf `index` to its peak
+pub fn proof_nodes(index: u64, size: u64) -> Option<Vec<(u32, u64)>> {
+    let (_, height, _) = find_mountain( index, size )?;
+
+    Some(
+        (0..height)
+            .map(|h| (h, (index >> h) ^ 1) )
+            .collect()
+    )
+}
+
+/// Verify that `leaf` is the leaf at `index` of an MMR of `size` leaves with `root`
+///
+/// `siblings` are the nodes at the addresses given by [`proof_nodes`] and `peaks` are all of the
+/// MMR's peaks.
+pub fn verify_inclusion(
+    index: u64,
+    size: u64,
+    leaf: &[u8; 32],
+    siblings: &[[u8; 32]],
+    peaks: &[[u8; 32]],
+    root: &[u8; 32],
+) -> bool {
+    let (position, height, _) = match find_mountain( index, size ) {
+        Some(mountain) => mountain,
+        None => return false,
+    };
+
+    if siblings.len() != height as usize || peaks.len() != mountain_heights( size ).len() {
+        return false;
+    }
+
+    let peak = siblings.iter().enumerate()
+        .fold( *leaf, |node, (h, sibling)| match (index >> h) & 1 {
+            0 => node_hash( &node, sibling ),
+            _ => node_hash( sibling, &node ),
+        });
+
+    peaks[ position ] == peak && bag_peaks( peaks ) == *root
+}
+
+
+/// An in-memory MMR that keeps every node
+///
+/// Useful for building proofs off-chain; the zome only ever keeps the peaks and the nodes created
+/// by each append.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleMountainRange {
+    nodes: Vec<Vec<[u8; 32]>>,
+    peaks: Vec<[u8; 32]>,
+    size: u64,
+}
+
+impl MerkleMountainRange {
+    /// Create an empty MMR
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a leaf and return its index
+    pub fn append(&mut self, leaf: [u8; 32]) -> u64 {
+        let (nodes, peaks) = append( &self.peaks, self.size, leaf );
+
+        for (height, node) in nodes.into_iter().enumerate() {
+            if self.nodes.len() <= height {
+                self.nodes.push( Vec::new() );
+            }
+            self.nodes[ height ].push( node );
+        }
+
+        self.peaks = peaks;
+        self.size += 1;
+        self.size - 1
+    }
+
+    /// The number of leaves appended so far
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The peaks from left to right (one per set bit of [`size`](Self::size))
+    pub fn peaks(&self) -> &[[u8; 32]] {
+        &self.peaks
+    }
+
+    /// The bagged peaks (see [`bag_peaks`])
+    pub fn root(&self) -> [u8; 32] {
+        bag_peaks( &self.peaks )
+    }
+
+    /// Get the node at `(height, index)`
+    pub fn node(&self, height: u32, index: u64) -> Option<[u8; 32]> {
+        self.nodes.get( height as usize )?
+            .get( index as usize )
+            .cloned()
+    }
+
+    /// Get the sibling path for the leaf at `index`
+    pub fn proof(&self, index: u64) -> Option<Vec<[u8; 32]>> {
+        proof_nodes( index, self.size )?.into_iter()
+            .map(|(height, index)| self.node( height, index ) )
+            .collect()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_log;
+
+    fn leaf(n: u64) -> [u8; 32] {
+        merkle_log::leaf_hash( &n.to_le_bytes() )
+    }
+
+    #[test]
+    fn test_peaks_follow_size() {
+        let mut mmr = MerkleMountainRange::new();
+
+        for n in 0..100 {
+            mmr.append( leaf( n ) );
+
+            assert_eq!( mmr.peaks().len(), mmr.size().count_ones() as usize );
+        }
+    }
+
+    #[test]
+    fn test_power_of_two_matches_merkle_log() {
+        let mut mmr = MerkleMountainRange::new();
+        let mut leaves = Vec::new();
+
+        for n in 0..64 {
+            mmr.append( leaf( n ) );
+            leaves.push( leaf( n ) );
+
+            if mmr.size().is_power_of_two() {
+                assert_eq!( mmr.root(), merkle_log::root( &leaves ) );
+            }
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let mut mmr = MerkleMountainRange::new();
+
+        for n in 0..50 {
+            mmr.append( leaf( n ) );
+            let root = mmr.root();
+
+            for index in 0..mmr.size() {
+                let siblings = mmr.proof( index ).unwrap();
+
+                assert!(
+                    verify_inclusion( index, mmr.size(), &leaf( index ), &siblings, mmr.peaks(), &root ),
+                    "proof failed for leaf {} of {}", index, mmr.size()
+                );
+                assert!( !verify_inclusion( index, mmr.size(), &leaf( index + 1 ), &siblings, mmr.peaks(), &root ) );
+            }
+
+            assert_eq!( mmr.proof( mmr.size() ), None );
+        }
+    }
+
+    #[test]
+    fn test_created_by() {
+        let mut mmr = MerkleMountainRange::new();
+        let mut created = Vec::new();
+
+        for n in 0..32 {
+            let size = mmr.size();
+            created.push( append( mmr.peaks(), size, leaf( n ) ).0 );
+            mmr.append( leaf( n ) );
+        }
+
+        for height in 0..5 {
+            for index in 0..(32 >> height) {
+                assert_eq!(
+                    created[ created_by( height, index ) as usize ][ height as usize ],
+                    mmr.node( height, index ).unwrap()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! SD-JWT style selective disclosure of a tree's data blocks
+//!
+//! Each [`LeafDataBlock`] becomes a disclosure of `[salt, label, value]` and the issuer signs a
+//! JWT that lists the digest of every disclosure.  A presentation is the JWT followed by the
+//! disclosures being revealed, each terminated by `~`.  The salts are the same ones used for the
+//! Merkle leaves, so both flows can be offered for the same tree.
+
+use std::convert::TryInto;
+use std::collections::BTreeMap;
+use sha2::{ Sha256, Digest };
+use crate::hdi;
+use crate::hdi_extensions::guest_error;
+use hdi::prelude::*;
+use crate::hdk::prelude::sign_raw;
+use crate::{
+    encoding,
+    w3c,
+    LeafDataBlock,
+    MerkleHash,
+};
+
+
+/// The hash algorithm used for disclosure digests
+pub const SD_ALG: &str = "sha-256";
+
+/// The JWT header for SD-JWTs signed with an agent key
+const JWT_HEADER: &str = r#"{"alg":"EdDSA","typ":"vc+sd-jwt"}"#;
+
+
+fn json_error(err: serde_json::Error) -> WasmError {
+    guest_error!(format!("SD-JWT JSON error: {}", err ))
+}
+
+
+fn base64url_decode(text: &str) -> ExternResult<Vec<u8>> {
+    encoding::base64url_decode( text )
+        .ok_or(guest_error!(format!("Invalid base64url '{}'", text )))
+}
+
+
+/// Encode a data block as an SD-JWT disclosure
+pub fn disclosure(block: &LeafDataBlock) -> ExternResult<String> {
+    let json = serde_json::to_vec( &( encoding::base64url_encode( &block.salt ), &block.label, &block.value ) )
+        .map_err( json_error )?;
+
+    Ok( encoding::base64url_encode( &json ) )
+}
+
+
+/// Decode a disclosure into its salt, label and value
+pub fn decode_disclosure(disclosure: &str) -> ExternResult<(String, String, serde_json::Value)> {
+    serde_json::from_slice( &base64url_decode( disclosure )? )
+        .map_err( json_error )
+}
+
+
+/// Get the digest of a disclosure as it appears in the `_sd` claim
+pub fn disclosure_digest(disclosure: &str) -> String {
+    encoding::base64url_encode( &Sha256::digest( disclosure.as_bytes() ) )
+}
+
+
+/// The signed claims of an SD-JWT
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SdJwtClaims {
+    /// The issuer's `did:key`
+    pub iss: String,
+    /// When the SD-JWT was issued (seconds since the epoch)
+    pub iat: i64,
+    /// The hex encoded root of the tree that the disclosures belong to
+    pub merkle_root: String,
+    /// The digests of every disclosure, sorted so they don't reveal the label order
+    #[serde(rename = "_sd")]
+    pub sd: Vec<String>,
+    /// Always [`SD_ALG`]
+    #[serde(rename = "_sd_alg")]
+    pub sd_alg: String,
+}
+
+
+/// An issued SD-JWT with every disclosure
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SdJwt {
+    /// The signed JWT
+    pub jwt: String,
+    /// A disclosure for each data block
+    pub disclosures: Vec<String>,
+}
+
+impl SdJwt {
+    /// Sign an SD-JWT for the given data blocks as `issuer`
+    ///
+    /// The issuer must be the agent running the current zome call.
+    pub fn issue(issuer: &AgentPubKey, data_blocks: &[LeafDataBlock], root: &MerkleHash, issued_at: &Timestamp) -> ExternResult<Self> {
+        let disclosures = data_blocks.iter()
+            .map( disclosure )
+            .collect::<ExternResult<Vec<String>>>()?;
+        let mut sd : Vec<String> = disclosures.iter()
+            .map(|disclosure| disclosure_digest( disclosure ) )
+            .collect();
+        sd.sort();
+
+        let claims = SdJwtClaims {
+            iss: w3c::did_key( issuer ),
+            iat: issued_at.as_micros() / 1_000_000,
+            merkle_root: root.to_hex(),
+            sd,
+            sd_alg: SD_ALG.to_string(),
+        };
+        let signing_input = format!(
+            "{}.{}",
+            encoding::base64url_encode( JWT_HEADER.as_bytes() ),
+            encoding::base64url_encode( &serde_json::to_vec( &claims ).map_err( json_error )? ),
+        );
+        let signature = sign_raw( issuer.to_owned(), signing_input.as_bytes().to_vec() )?;
+
+        Ok(
+            SdJwt {
+                jwt: format!("{}.{}", signing_input, encoding::base64url_encode( &signature.0 ) ),
+                disclosures,
+            }
+        )
+    }
+
+    /// Create a presentation that only reveals the given labels
+    pub fn present(&self, labels: &[String]) -> ExternResult<String> {
+        let mut presentation = format!("{}~", self.jwt );
+
+        for disclosure in self.disclosures.iter() {
+            let (_, label, _) = decode_disclosure( disclosure )?;
+
+            if labels.contains( &label ) {
+                presentation.push_str( disclosure );
+                presentation.push('~');
+            }
+        }
+
+        Ok( presentation )
+    }
+}
+
+
+/// The result of verifying an SD-JWT presentation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifiedSdJwt {
+    /// The agent that signed the SD-JWT
+    pub issuer: AgentPubKey,
+    /// The signed claims
+    pub claims: SdJwtClaims,
+    /// The revealed values by label
+    pub disclosed: BTreeMap<String, serde_json::Value>,
+}
+
+
+/// Check the issuer signature and that every revealed disclosure was signed for
+pub fn verify_presentation(presentation: &str) -> ExternResult<VerifiedSdJwt> {
+    let mut parts = presentation.split('~');
+    let jwt = parts.next().unwrap_or_default();
+    let segments : Vec<&str> = jwt.split('.').collect();
+
+    if segments.len() != 3 {
+        return Err(guest_error!(format!("SD-JWT must have 3 segments; found {}", segments.len() )));
+    }
+
+    let header : serde_json::Value = serde_json::from_slice( &base64url_decode( segments[0] )? )
+        .map_err( json_error )?;
+
+    if header["alg"] != "EdDSA" {
+        return Err(guest_error!(format!("Unsupported SD-JWT algorithm: {}", header["alg"] )));
+    }
+
+    let claims : SdJwtClaims = serde_json::from_slice( &base64url_decode( segments[1] )? )
+        .map_err( json_error )?;
+
+    if claims.sd_alg != SD_ALG {
+        return Err(guest_error!(format!("Unsupported disclosure digest algorithm: {}", claims.sd_alg )));
+    }
+
+    let issuer = w3c::agent_from_did_key( &claims.iss )?;
+    let signature : [u8; 64] = base64url_decode( segments[2] )?
+        .try_into()
+        .map_err(|_| guest_error!(format!("SD-JWT signature must be 64 bytes")))?;
+
+    if !verify_signature_raw(
+        issuer.clone(),
+        Signature( signature ),
+        format!("{}.{}", segments[0], segments[1] ).into_bytes(),
+    )? {
+        return Err(guest_error!(format!("SD-JWT signature is not valid for issuer {}", issuer )));
+    }
+
+    let mut disclosed = BTreeMap::new();
+
+    for disclosure in parts.filter(|part| !part.is_empty() ) {
+        if !claims.sd.contains( &disclosure_digest( disclosure ) ) {
+            return Err(guest_error!(format!("Disclosure is not part of the SD-JWT: {}", disclosure )));
+        }
+
+        let (_, label, value) = decode_disclosure( disclosure )?;
+        disclosed.insert( label, value );
+    }
+
+    Ok(
+        VerifiedSdJwt {
+            issuer,
+            claims,
+            disclosed,
+        }
+    )
+}
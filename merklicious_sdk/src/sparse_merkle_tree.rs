@@ -0,0 +1,294 @@
+//! A fixed depth (256) sparse Merkle tree where each leaf's position is the SHA-256 hash of its
+//! label.
+//!
+//! Unlike the dense [`rs_merkle::MerkleTree`], a leaf's position does not depend on the other
+//! leaves, so a proof does not reveal the leaf index or the total number of leaves.  Because every
+//! possible key has a position, the same structure can prove that a label is *not* in the tree.
+//!
+//! Empty subtrees are represented by [`EMPTY_HASH`] at every level, so only non-empty siblings
+//! need to be included in a proof.
+use std::collections::BTreeMap;
+use serde::{ Serialize, Deserialize };
+use sha2::{ Sha256, Digest };
+
+
+/// The number of levels between the root and a leaf
+pub const DEPTH: usize = 256;
+
+/// The hash used for an empty leaf and for any subtree that contains no leaves
+pub const EMPTY_HASH: [u8; 32] = [0; 32];
+
+
+/// Derive the tree key for the given label
+pub fn label_key(label: &str) -> [u8; 32] {
+    <[u8; 32]>::from( Sha256::digest( label.as_bytes() ) )
+}
+
+/// Hash a non-empty leaf so that its value is bound to its key
+pub fn hash_leaf(key: &[u8; 32], value: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update( key );
+    hasher.update( value );
+    <[u8; 32]>::from( hasher.finalize() )
+}
+
+/// Hash two child nodes; the parent of two empty subtrees is also empty
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if *left == EMPTY_HASH && *right == EMPTY_HASH {
+        return EMPTY_HASH;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update( left );
+    hasher.update( right );
+    <[u8; 32]>::from( hasher.finalize() )
+}
+
+/// Get the path direction for `key` at the given depth (`true` means right)
+fn key_bit(key: &[u8; 32], depth: usize) -> bool {
+    ( key[ depth / 8 ] >> ( 7 - (depth % 8) ) ) & 1 == 1
+}
+
+fn set_bit(bitmap: &mut [u8; 32], depth: usize) {
+    bitmap[ depth / 8 ] |= 1 << ( 7 - (depth % 8) );
+}
+
+
+/// A sparse Merkle tree holding `key -> value` leaves
+#[derive(Clone, Debug, Default)]
+pub struct SparseMerkleTree {
+    leaves: BTreeMap<[u8; 32], [u8; 32]>,
+}
+
+impl SparseMerkleTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a leaf value at the given key, returning the previous value if there was one
+    pub fn insert(&mut self, key: [u8; 32], value: [u8; 32]) -> Option<[u8; 32]> {
+        self.leaves.insert( key, value )
+    }
+
+    /// Get the leaf value at the given key
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.leaves.get( key )
+    }
+
+    /// The number of non-empty leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Check if the tree has no leaves
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Calculate the root hash
+    pub fn root(&self) -> [u8; 32] {
+        let entries : Vec<([u8; 32], [u8; 32])> = self.leaves.iter()
+            .map(|(key, value)| (*key, *value) )
+            .collect();
+
+        subtree_root( &entries, 0 )
+    }
+
+    /// Create a proof for the given key
+    ///
+    /// The same proof type is used for inclusion and exclusion; which one it proves depends on
+    /// whether the key has a value in this tree.
+    pub fn proof(&self, key: &[u8; 32]) -> SparseMerkleProof {
+        let entries : Vec<([u8; 32], [u8; 32])> = self.leaves.iter()
+            .map(|(key, value)| (*key, *value) )
+            .collect();
+        let mut bitmap = [0; 32];
+        let mut siblings = Vec::new();
+        let mut path = &entries[..];
+
+        for depth in 0..DEPTH {
+            if path.is_empty() {
+                break;
+            }
+
+            let split = path.partition_point(|(k, _)| !key_bit( k, depth ) );
+            let (left, right) = path.split_at( split );
+            let (own, other) = match key_bit( key, depth ) {
+                true => (right, left),
+                false => (left, right),
+            };
+            let sibling = subtree_root( other, depth + 1 );
+
+            if sibling != EMPTY_HASH {
+                set_bit( &mut bitmap, depth );
+                siblings.push( sibling );
+            }
+
+            path = own;
+        }
+
+        SparseMerkleProof {
+            bitmap,
+            siblings,
+        }
+    }
+}
+
+/// Calculate the root of a subtree whose entries are sorted and all share the same path prefix up
+/// to `depth`
+fn subtree_root(entries: &[([u8; 32], [u8; 32])], depth: usize) -> [u8; 32] {
+    if entries.is_empty() {
+        return EMPTY_HASH;
+    }
+
+    if depth == DEPTH {
+        let (key, value) = &entries[0];
+        return hash_leaf( key, value );
+    }
+
+    let split = entries.partition_point(|(k, _)| !key_bit( k, depth ) );
+    let (left, right) = entries.split_at( split );
+
+    hash_pair(
+        &subtree_root( left, depth + 1 ),
+        &subtree_root( right, depth + 1 ),
+    )
+}
+
+
+/// A compressed sparse Merkle proof
+///
+/// Bit `d` of `bitmap` is set when the sibling at depth `d` is non-empty, in which case its hash
+/// is the next item of `siblings` (ordered from the root down).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SparseMerkleProof {
+    /// Flags for which siblings are non-empty
+    pub bitmap: [u8; 32],
+    /// The non-empty sibling hashes ordered from the root down
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl SparseMerkleProof {
+    /// Calculate the root implied by this proof for the given key and optional leaf value
+    ///
+    /// Returns `None` if the number of siblings does not match the bitmap.
+    pub fn compute_root(&self, key: &[u8; 32], value: Option<&[u8; 32]>) -> Option<[u8; 32]> {
+        let expected_siblings = self.bitmap.iter()
+            .map(|byte| byte.count_ones() as usize )
+            .sum::<usize>();
+
+        if expected_siblings != self.siblings.len() {
+            return None;
+        }
+
+        let mut node = match value {
+            Some(value) => hash_leaf( key, value ),
+            None => EMPTY_HASH,
+        };
+        let mut siblings = self.siblings.iter().rev();
+
+        for depth in (0..DEPTH).rev() {
+            let sibling = match key_bit( &self.bitmap, depth ) {
+                true => *siblings.next()?,
+                false => EMPTY_HASH,
+            };
+
+            node = match key_bit( key, depth ) {
+                true => hash_pair( &sibling, &node ),
+                false => hash_pair( &node, &sibling ),
+            };
+        }
+
+        Some( node )
+    }
+
+    /// Verify that `key` has the leaf `value` in the tree with the given root
+    pub fn verify_inclusion(&self, root: &[u8; 32], key: &[u8; 32], value: &[u8; 32]) -> bool {
+        self.compute_root( key, Some( value ) ).as_ref() == Some( root )
+    }
+
+    /// Verify that `key` has no leaf in the tree with the given root
+    pub fn verify_exclusion(&self, root: &[u8; 32], key: &[u8; 32]) -> bool {
+        self.compute_root( key, None ).as_ref() == Some( root )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(n: u8) -> [u8; 32] {
+        [n; 32]
+    }
+
+    fn tree(labels: &[&str]) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+        for (i, label) in labels.iter().enumerate() {
+            tree.insert( label_key( label ), leaf( i as u8 + 1 ) );
+        }
+        tree
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = SparseMerkleTree::new();
+        let key = label_key("name");
+
+        assert_eq!( tree.root(), EMPTY_HASH );
+        assert!( tree.proof( &key ).verify_exclusion( &EMPTY_HASH, &key ) );
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let tree = tree( &["name.first", "name.last", "date_of_birth", "organ_donor"] );
+        let root = tree.root();
+
+        for (i, label) in ["name.first", "name.last", "date_of_birth", "organ_donor"].iter().enumerate() {
+            let key = label_key( label );
+            let proof = tree.proof( &key );
+
+            assert!( proof.verify_inclusion( &root, &key, &leaf( i as u8 + 1 ) ) );
+            assert!( !proof.verify_inclusion( &root, &key, &leaf( 99 ) ) );
+            assert!( !proof.verify_exclusion( &root, &key ) );
+        }
+    }
+
+    #[test]
+    fn test_exclusion_proof() {
+        let tree = tree( &["name.first", "name.last"] );
+        let root = tree.root();
+        let key = label_key("date_of_birth");
+        let proof = tree.proof( &key );
+
+        assert!( proof.verify_exclusion( &root, &key ) );
+        assert!( !proof.verify_inclusion( &root, &key, &leaf( 1 ) ) );
+        assert!( !proof.verify_exclusion( &root, &label_key("name.first") ) );
+    }
+
+    #[test]
+    fn test_root_is_order_independent() {
+        assert_eq!(
+            tree( &["a", "b", "c"] ).root(),
+            {
+                let mut tree = SparseMerkleTree::new();
+                tree.insert( label_key("c"), leaf( 3 ) );
+                tree.insert( label_key("a"), leaf( 1 ) );
+                tree.insert( label_key("b"), leaf( 2 ) );
+                tree.root()
+            }
+        );
+    }
+
+    #[test]
+    fn test_malformed_proof() {
+        let tree = tree( &["a", "b"] );
+        let key = label_key("a");
+        let mut proof = tree.proof( &key );
+        proof.siblings.push( leaf( 7 ) );
+
+        assert_eq!( proof.compute_root( &key, Some( &leaf( 1 ) ) ), None );
+    }
+}
@@ -0,0 +1,275 @@
+//! Versioned string encoding for sending proofs outside of Holochain
+//!
+//! An encoded value is a [multibase](https://github.com/multiformats/multibase) string whose
+//! decoded bytes start with [`TRANSPORT_VERSION`] followed by the msgpack encoding of the value.
+//! Hashes (eg. roots) are encoded the same way except the version is followed by the 32 raw
+//! bytes.  The [`serde_hex`] and [`serde_base64`] adapters are for embedding hash fields in JSON.
+
+use serde::{ Serialize, de::DeserializeOwned };
+use crate::encoding;
+
+
+/// The version byte at the start of every encoded value
+pub const TRANSPORT_VERSION: u8 = 1;
+
+
+/// Errors from decoding a transport string
+#[derive(Debug, thiserror::Error)]
+pub enum TransportError {
+    #[error("Transport string is empty")]
+    Empty,
+    #[error("Unsupported multibase prefix '{0}'")]
+    UnsupportedBase(char),
+    #[error("Invalid {0:?} encoding")]
+    InvalidEncoding(Multibase),
+    #[error("Unsupported transport version {0}; expected {}", TRANSPORT_VERSION)]
+    UnsupportedVersion(u8),
+    #[error("Expected a 32 byte hash; found {0} bytes")]
+    InvalidHashLength(usize),
+    #[error("Failed to encode value: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Failed to decode value: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+
+/// The multibase encodings that can be decoded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Multibase {
+    /// Lowercase hex (`f`)
+    Base16,
+    /// Bitcoin base58 (`z`)
+    Base58Btc,
+    /// URL safe base64 without padding (`u`)
+    Base64Url,
+}
+
+impl Multibase {
+    /// The multibase prefix character
+    pub fn prefix(&self) -> char {
+        match self {
+            Multibase::Base16 => 'f',
+            Multibase::Base58Btc => 'z',
+            Multibase::Base64Url => 'u',
+        }
+    }
+
+    /// Get the encoding for a multibase prefix character
+    pub fn from_prefix(prefix: char) -> Result<Self, TransportError> {
+        match prefix {
+            'f' => Ok( Multibase::Base16 ),
+            'z' => Ok( Multibase::Base58Btc ),
+            'u' => Ok( Multibase::Base64Url ),
+            other => Err( TransportError::UnsupportedBase( other ) ),
+        }
+    }
+}
+
+
+/// Encode bytes as a multibase string
+pub fn multibase_encode(base: Multibase, bytes: &[u8]) -> String {
+    let text = match base {
+        Multibase::Base16 => hex::encode( bytes ),
+        Multibase::Base58Btc => encoding::base58btc_encode( bytes ),
+        Multibase::Base64Url => encoding::base64url_encode( bytes ),
+    };
+
+    format!("{}{}", base.prefix(), text )
+}
+
+
+/// Decode a multibase string
+pub fn multibase_decode(text: &str) -> Result<Vec<u8>, TransportError> {
+    let prefix = text.chars().next()
+        .ok_or( TransportError::Empty )?;
+    let base = Multibase::from_prefix( prefix )?;
+    let text = &text[ prefix.len_utf8().. ];
+
+    match base {
+        Multibase::Base16 => hex::decode( text ).ok(),
+        Multibase::Base58Btc => encoding::base58btc_decode( text ),
+        Multibase::Base64Url => encoding::base64url_decode( text ),
+    }.ok_or( TransportError::InvalidEncoding( base ) )
+}
+
+
+fn versioned(mut bytes: Vec<u8>) -> Vec<u8> {
+    bytes.insert( 0, TRANSPORT_VERSION );
+    bytes
+}
+
+
+fn unversioned(text: &str) -> Result<Vec<u8>, TransportError> {
+    let mut bytes = multibase_decode( text )?;
+
+    match bytes.first() {
+        Some(&TRANSPORT_VERSION) => Ok( bytes.split_off( 1 ) ),
+        Some(version) => Err( TransportError::UnsupportedVersion( *version ) ),
+        None => Err( TransportError::Empty ),
+    }
+}
+
+
+/// Encode a value using the given base
+pub fn encode_with<T: Serialize>(base: Multibase, value: &T) -> Result<String, TransportError> {
+    Ok( multibase_encode( base, &versioned( rmp_serde::to_vec_named( value )? ) ) )
+}
+
+
+/// Encode a value (eg. a `LeafProofPayload` or `PresentationPayload`) as base64url
+pub fn encode<T: Serialize>(value: &T) -> Result<String, TransportError> {
+    encode_with( Multibase::Base64Url, value )
+}
+
+
+/// Decode a value made by [`encode`] or [`encode_with`]
+pub fn decode<T: DeserializeOwned>(text: &str) -> Result<T, TransportError> {
+    Ok( rmp_serde::from_slice( &unversioned( text )? )? )
+}
+
+
+/// Encode a hash (eg. a Merkle root) as base64url
+pub fn encode_hash(hash: &[u8; 32]) -> String {
+    multibase_encode( Multibase::Base64Url, &versioned( hash.to_vec() ) )
+}
+
+
+/// Decode a hash made by [`encode_hash`]
+pub fn decode_hash(text: &str) -> Result<[u8; 32], TransportError> {
+    let bytes = unversioned( text )?;
+    let mut hash = [0; 32];
+
+    if bytes.len() != hash.len() {
+        return Err( TransportError::InvalidHashLength( bytes.len() ) );
+    }
+
+    hash.copy_from_slice( &bytes );
+
+    Ok( hash )
+}
+
+
+fn hash_from_bytes<E: serde::de::Error>(bytes: Option<Vec<u8>>) -> Result<[u8; 32], E> {
+    let bytes = bytes.ok_or( E::custom("invalid hash encoding") )?;
+    let mut hash = [0; 32];
+
+    if bytes.len() != hash.len() {
+        return Err( E::invalid_length( bytes.len(), &"32 bytes" ) );
+    }
+
+    hash.copy_from_slice( &bytes );
+
+    Ok( hash )
+}
+
+
+macro_rules! hash_serde_adapter {
+    ( $name:ident, $desc:literal, $encode:path, $decode:path ) => {
+        #[doc = concat!("Serialize `[u8; 32]` fields as ", $desc, " strings (`#[serde(with = \"transport::", stringify!($name), "\")]`)")]
+        pub mod $name {
+            use serde::{ Serialize, Serializer, Deserialize, Deserializer };
+
+            pub fn serialize<S: Serializer>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+                $encode( hash ).serialize( serializer )
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+                crate::transport::hash_from_bytes( $decode( &String::deserialize( deserializer )? ) )
+            }
+
+            #[doc = concat!("Serialize `Vec<[u8; 32]>` fields as lists of ", $desc, " strings")]
+            pub mod list {
+                use serde::{ Serialize, Serializer, Deserialize, Deserializer };
+
+                pub fn serialize<S: Serializer>(hashes: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+                    hashes.iter()
+                        .map(|hash| $encode( hash ) )
+                        .collect::<Vec<String>>()
+                        .serialize( serializer )
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error> {
+                    Vec::<String>::deserialize( deserializer )?
+                        .iter()
+                        .map(|text| crate::transport::hash_from_bytes( $decode( text ) ) )
+                        .collect()
+                }
+            }
+        }
+    };
+}
+
+fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    hex::decode( text ).ok()
+}
+
+hash_serde_adapter!( serde_hex, "hex", hex::encode, crate::transport::hex_decode );
+hash_serde_adapter!( serde_base64, "base64url", crate::encoding::base64url_encode, crate::encoding::base64url_decode );
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Proof {
+        #[serde(with = "serde_hex::list")]
+        proof: Vec<[u8; 32]>,
+        #[serde(with = "serde_base64")]
+        root: [u8; 32],
+        index: u64,
+    }
+
+    fn proof() -> Proof {
+        Proof {
+            proof: vec![ [1; 32], [255; 32] ],
+            root: [7; 32],
+            index: 3,
+        }
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let encoded = encode( &proof() ).unwrap();
+
+        assert!( encoded.starts_with('u') );
+        assert_eq!( decode::<Proof>( &encoded ).unwrap(), proof() );
+
+        for base in [ Multibase::Base16, Multibase::Base58Btc ].iter() {
+            let encoded = encode_with( *base, &proof() ).unwrap();
+
+            assert!( encoded.starts_with( base.prefix() ) );
+            assert_eq!( decode::<Proof>( &encoded ).unwrap(), proof() );
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!( matches!( decode::<Proof>( "" ), Err(TransportError::Empty) ) );
+        assert!( matches!( decode::<Proof>( "xabc" ), Err(TransportError::UnsupportedBase('x')) ) );
+        assert!( matches!( decode::<Proof>( "u!!" ), Err(TransportError::InvalidEncoding(Multibase::Base64Url)) ) );
+        assert!( matches!( decode::<Proof>( "f02" ), Err(TransportError::UnsupportedVersion(2)) ) );
+        assert!( matches!( decode_hash( "f0100" ), Err(TransportError::InvalidHashLength(1)) ) );
+    }
+
+    #[test]
+    fn test_hash_round_trip() {
+        let encoded = encode_hash( &[42; 32] );
+
+        assert_eq!( encoded.len(), 1 + 44 );
+        assert_eq!( decode_hash( &encoded ).unwrap(), [42; 32] );
+    }
+
+    #[test]
+    fn test_serde_adapters() {
+        let json = serde_json::to_value( proof() ).unwrap();
+
+        assert_eq!( json["proof"][0], hex::encode( [1; 32] ) );
+        assert_eq!( json["root"], encoding::base64url_encode( &[7; 32] ) );
+        assert_eq!( serde_json::from_value::<Proof>( json ).unwrap(), proof() );
+
+        assert!( serde_json::from_str::<Proof>( r#"{"proof":["00"],"root":"","index":0}"# ).is_err() );
+    }
+}
@@ -0,0 +1,363 @@
+//! Leaf hashing and proof verification without the HDK
+//!
+//! These are the functions behind `LeafDataBlock::hash`, `LeafInput::into_data_block` and
+//! `LeafProofPayload::verify`, so verifiers built without the `hdk` feature (eg. the browser
+//! package) get exactly the same results as the zome.
+//!
+//! Salts are derived from the leaf index as a 4 byte little-endian integer, which is the size of
+//! `usize` in the zome's `wasm32` target, so native builds derive the same salts.
+
+use std::convert::TryFrom;
+use hmac::{ Hmac, Mac };
+use rs_merkle::{ MerkleTree, MerkleProof, algorithms };
+use serde::{ Serialize, Deserialize };
+use sha2::{ Sha256, Digest };
+use crate::{
+    ethereum,
+    MerkleHash,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+
+/// Errors from hashing or verifying a leaf
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum VerifyError {
+    #[error("Failed to encode leaf '{0}': {1}")]
+    Encode(String, String),
+    #[error("Leaf index {0} is too large")]
+    IndexTooLarge(u64),
+}
+
+
+/// The fields of a `LeafDataBlock`, serialized the same way
+#[derive(Serialize)]
+struct LeafDataRef<'a> {
+    label: &'a str,
+    value: &'a rmpv::Value,
+    #[serde(with = "serde_bytes")]
+    salt: &'a [u8],
+}
+
+
+/// Get the SHA-256 leaf hash of a data block
+pub fn leaf_hash(label: &str, value: &rmpv::Value, salt: &[u8]) -> Result<[u8; 32], VerifyError> {
+    let bytes = rmp_serde::to_vec( &LeafDataRef { label, value, salt } )
+        .map_err(|err| VerifyError::Encode( label.to_string(), err.to_string() ) )?;
+
+    Ok( Sha256::digest( &bytes ).into() )
+}
+
+
+/// The hash function used for a tree's leaves and nodes
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// SHA-256 leaves and an [`rs_merkle`] tree
+    #[default]
+    Sha256,
+    /// Keccak-256 leaves and an OpenZeppelin `StandardMerkleTree` (see [`ethereum`])
+    Keccak256,
+}
+
+impl HashAlgorithm {
+    /// Check if this is the default [`HashAlgorithm::Sha256`]
+    pub fn is_sha256(&self) -> bool {
+        *self == HashAlgorithm::Sha256
+    }
+
+    /// Get the leaf hash of a data block's fields
+    pub fn hash_leaf(&self, label: &str, value: &rmpv::Value, salt: &[u8]) -> Result<[u8; 32], VerifyError> {
+        match self {
+            HashAlgorithm::Sha256 => leaf_hash( label, value, salt ),
+            HashAlgorithm::Keccak256 => {
+                let value = rmp_serde::to_vec( value )
+                    .map_err(|err| VerifyError::Encode( label.to_string(), err.to_string() ) )?;
+
+                Ok( ethereum::leaf_hash( &ethereum::abi_encode_dynamic( &[ label.as_bytes(), &value, salt ] ) ) )
+            },
+        }
+    }
+
+    /// Get the root of a standard tree built from `leaves`
+    pub fn root(&self, leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+        match self {
+            HashAlgorithm::Sha256 => calculate_root( leaves ),
+            HashAlgorithm::Keccak256 => ethereum::StandardMerkleTree::from_leaves( leaves )
+                .map(|tree| tree.root() ),
+        }
+    }
+
+    /// Check that `proof` leads from `leaf` at `index` to `root`
+    ///
+    /// Keccak-256 trees hash pairs in sorted order, so their proofs don't depend on `index` or
+    /// `total_leaves`.
+    pub fn verify_proof(&self, root: &[u8; 32], index: u64, leaf: &[u8; 32], proof: &[[u8; 32]], total_leaves: u64) -> bool {
+        match self {
+            HashAlgorithm::Sha256 => verify_proof( root, index, leaf, proof, total_leaves ),
+            HashAlgorithm::Keccak256 => ethereum::verify( root, leaf, proof ),
+        }
+    }
+}
+
+
+fn index_bytes(index: usize) -> Result<[u8; 4], VerifyError> {
+    u32::try_from( index )
+        .map( u32::to_le_bytes )
+        .map_err(|_| VerifyError::IndexTooLarge( index as u64 ) )
+}
+
+
+/// Derive the salt of the leaf at `index` from a tree's entropy
+pub fn derive_salt(entropy: &[u8], index: usize) -> Result<Vec<u8>, VerifyError> {
+    // HMAC accepts keys of any length
+    let mut hmac = HmacSha256::new_from_slice( entropy ).unwrap();
+
+    hmac.update( &index_bytes( index )? );
+
+    Ok( hmac.finalize().into_bytes().to_vec() )
+}
+
+
+/// Derive the padding leaf at `index` from a tree's entropy
+pub fn padding_leaf(entropy: &[u8], index: usize) -> Result<[u8; 32], VerifyError> {
+    let mut hmac = HmacSha256::new_from_slice( entropy ).unwrap();
+
+    hmac.update( b"padding" );
+    hmac.update( &index_bytes( index )? );
+
+    Ok( hmac.finalize().into_bytes().into() )
+}
+
+
+/// Get the root of a standard tree
+pub fn calculate_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    MerkleTree::<algorithms::Sha256>::from_leaves( leaves ).root()
+}
+
+
+/// Check that `proof` leads from `leaf` at `index` to `root`
+pub fn verify_proof(root: &[u8; 32], index: u64, leaf: &[u8; 32], proof: &[[u8; 32]], total_leaves: u64) -> bool {
+    MerkleProof::<algorithms::Sha256>::new( proof.to_vec() )
+        .verify( *root, &[ index as usize ], &[ *leaf ], total_leaves as usize )
+}
+
+
+/// Get the root that `proof` leads to from `leaf` at `index`
+pub fn proof_root(index: u64, leaf: &[u8; 32], proof: &[[u8; 32]], total_leaves: u64) -> Option<[u8; 32]> {
+    MerkleProof::<algorithms::Sha256>::new( proof.to_vec() )
+        .root( &[ index as usize ], &[ *leaf ], total_leaves as usize )
+        .ok()
+}
+
+
+/// The context tag and version that every attestation signature starts with
+///
+/// This keeps an attestation signature from being valid for any other message signed by the same
+/// agent key.
+pub const ATTESTATION_CONTEXT: &[u8] = b"merklicious:attestation:v1";
+
+
+/// Get the bytes an issuer signs for an `AttestationEntry`
+///
+/// This is [`ATTESTATION_CONTEXT`] followed by the holder (the 39 byte agent public key) and the
+/// root.
+pub fn attestation_message(holder: &[u8], root: &[u8; 32]) -> Vec<u8> {
+    [ ATTESTATION_CONTEXT, holder, root ].concat()
+}
+
+
+/// A revealed data block (the same shape as `LeafDataBlock`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeafData {
+    pub label: String,
+    pub value: rmpv::Value,
+    #[serde(with = "serde_bytes")]
+    pub salt: Vec<u8>,
+}
+
+impl LeafData {
+    /// Get the leaf hash of this data block
+    pub fn hash(&self) -> Result<[u8; 32], VerifyError> {
+        leaf_hash( &self.label, &self.value, &self.salt )
+    }
+}
+
+
+/// A leaf proof (the same shape as `LeafProofPayload`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeafProof {
+    pub proof: Vec<MerkleHash>,
+    pub index: u64,
+    pub target: LeafData,
+    pub leaf: MerkleHash,
+    pub root: MerkleHash,
+    pub total_leaves: u64,
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_sha256")]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl LeafProof {
+    /// Verify that `target` is the leaf at `index` of the tree with `root`
+    pub fn verify(&self) -> Result<bool, VerifyError> {
+        let algorithm = &self.hash_algorithm;
+
+        Ok(
+            algorithm.hash_leaf( &self.target.label, &self.target.value, &self.target.salt )? == self.leaf
+                && algorithm.verify_proof( &self.root, self.index, &self.leaf, &MerkleHash::to_raw_vec( &self.proof ), self.total_leaves )
+        )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_salt() {
+        // The index is always hashed as a u32 so native and wasm32 builds agree
+        let mut hmac = HmacSha256::new_from_slice( &[ 7; 32 ] ).unwrap();
+        hmac.update( &[ 3, 0, 0, 0 ] );
+
+        assert_eq!( derive_salt( &[ 7; 32 ], 3 ).unwrap(), hmac.finalize().into_bytes().to_vec() );
+        assert_ne!( derive_salt( &[ 7; 32 ], 3 ).unwrap(), derive_salt( &[ 7; 32 ], 4 ).unwrap() );
+        assert_ne!( padding_leaf( &[ 7; 32 ], 3 ).unwrap().to_vec(), derive_salt( &[ 7; 32 ], 3 ).unwrap() );
+    }
+
+    #[test]
+    fn test_verify() {
+        let blocks : Vec<LeafData> = (0..5)
+            .map(|index| LeafData {
+                label: format!("field_{}", index ),
+                value: rmpv::Value::from( index ),
+                salt: derive_salt( b"entropy", index as usize ).unwrap(),
+            })
+            .collect();
+        let leaves : Vec<[u8; 32]> = blocks.iter().map(|block| block.hash().unwrap() ).collect();
+        let root = calculate_root( &leaves ).unwrap();
+        let tree = MerkleTree::<algorithms::Sha256>::from_leaves( &leaves );
+
+        for (index, block) in blocks.iter().enumerate() {
+            let mut proof = LeafProof {
+                proof: MerkleHash::from_raw_vec( tree.proof( &[ index ] ).proof_hashes().to_vec() ),
+                index: index as u64,
+                target: block.clone(),
+                leaf: leaves[ index ].into(),
+                root: root.into(),
+                total_leaves: leaves.len() as u64,
+                hash_algorithm: HashAlgorithm::Sha256,
+            };
+
+            assert!( proof.verify().unwrap() );
+            assert_eq!( proof_root( proof.index, &proof.leaf, &MerkleHash::to_raw_vec( &proof.proof ), proof.total_leaves ), Some( root ) );
+
+            proof.target.value = rmpv::Value::from( "forged" );
+            assert!( !proof.verify().unwrap() );
+        }
+    }
+
+    #[test]
+    fn test_verify_keccak256() {
+        let algorithm = HashAlgorithm::Keccak256;
+        let blocks : Vec<LeafData> = (0..5)
+            .map(|index| LeafData {
+                label: format!("field_{}", index ),
+                value: rmpv::Value::from( index ),
+                salt: derive_salt( b"entropy", index as usize ).unwrap(),
+            })
+            .collect();
+        let leaves : Vec<[u8; 32]> = blocks.iter()
+            .map(|block| algorithm.hash_leaf( &block.label, &block.value, &block.salt ).unwrap() )
+            .collect();
+        let tree = ethereum::StandardMerkleTree::from_leaves( &leaves ).unwrap();
+
+        assert_eq!( algorithm.root( &leaves ), Some( tree.root() ) );
+
+        for (index, block) in blocks.iter().enumerate() {
+            let mut proof = LeafProof {
+                proof: MerkleHash::from_raw_vec( tree.proof( index ).unwrap() ),
+                index: index as u64,
+                target: block.clone(),
+                leaf: leaves[ index ].into(),
+                root: tree.root().into(),
+                total_leaves: leaves.len() as u64,
+                hash_algorithm: algorithm.clone(),
+            };
+
+            assert!( proof.verify().unwrap() );
+
+            // The same proof is not valid as a SHA-256 proof
+            proof.hash_algorithm = HashAlgorithm::Sha256;
+            assert!( !proof.verify().unwrap() );
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct VectorLeaf {
+        label: String,
+        value: serde_json::Value,
+        salt: String,
+        leaf: String,
+    }
+
+    #[derive(Deserialize)]
+    struct VectorCase {
+        name: String,
+        entropy: String,
+        leaves: Vec<VectorLeaf>,
+        #[serde(default)]
+        padding_leaves: Vec<String>,
+        root: String,
+        proofs: Vec<Vec<String>>,
+    }
+
+    #[derive(Deserialize)]
+    struct Vectors {
+        version: u32,
+        cases: Vec<VectorCase>,
+    }
+
+    fn hash_from_hex(text: &str) -> [u8; 32] {
+        MerkleHash::from_hex( text ).unwrap().0
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors : Vectors = serde_json::from_str( include_str!("../../tests/vectors/merklicious_v1.json") ).unwrap();
+
+        assert_eq!( vectors.version, 1 );
+
+        for case in vectors.cases.iter() {
+            let entropy = hex::decode( &case.entropy ).unwrap();
+            let root = hash_from_hex( &case.root );
+            let mut leaves = Vec::new();
+
+            for (index, leaf) in case.leaves.iter().enumerate() {
+                let value : rmpv::Value = serde_json::from_value( leaf.value.clone() ).unwrap();
+                let salt = derive_salt( &entropy, index ).unwrap();
+
+                assert_eq!( hex::encode( &salt ), leaf.salt, "{}: salt of '{}'", case.name, leaf.label );
+                assert_eq!( hex::encode( leaf_hash( &leaf.label, &value, &salt ).unwrap() ), leaf.leaf, "{}: leaf '{}'", case.name, leaf.label );
+
+                leaves.push( hash_from_hex( &leaf.leaf ) );
+            }
+
+            for (offset, padding) in case.padding_leaves.iter().enumerate() {
+                let index = case.leaves.len() + offset;
+
+                assert_eq!( hex::encode( padding_leaf( &entropy, index ).unwrap() ), *padding, "{}: padding leaf {}", case.name, index );
+
+                leaves.push( hash_from_hex( padding ) );
+            }
+
+            assert_eq!( calculate_root( &leaves ), Some( root ), "{}: root", case.name );
+
+            for (index, proof) in case.proofs.iter().enumerate() {
+                let proof : Vec<[u8; 32]> = proof.iter().map(|hash| hash_from_hex( hash ) ).collect();
+
+                assert!( verify_proof( &root, index as u64, &leaves[ index ], &proof, leaves.len() as u64 ), "{}: proof {}", case.name, index );
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! W3C Verifiable Presentations of Merkle disclosures
+//!
+//! A [`PresentationPayload`] becomes a presentation holding one credential whose subject is the
+//! revealed labels and whose proof is a [`MERKLE_DISCLOSURE_PROOF_TYPE`] with everything needed to
+//! rebuild each [`LeafProofPayload`].  Revealed values are carried as hex encoded msgpack because
+//! the leaf hashes cover the exact msgpack bytes; verification checks that the `credentialSubject`
+//! holds exactly those values.
+
+use std::collections::BTreeMap;
+use crate::hdi;
+use crate::hdi_extensions::guest_error;
+use hdi::prelude::*;
+use crate::{
+    encoding,
+    HashAlgorithm,
+    LeafDataBlock,
+    LeafProofPayload,
+    MerkleHash,
+    PresentationPayload,
+};
+
+
+/// The base JSON-LD context for Verifiable Credentials
+pub const VC_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// The proof type used for Merkle disclosures
+pub const MERKLE_DISCLOSURE_PROOF_TYPE: &str = "MerkliciousMerkleDisclosure2023";
+
+/// The multicodec prefix for an Ed25519 public key
+const ED25519_PUB_MULTICODEC: [u8; 2] = [ 0xed, 0x01 ];
+
+
+/// Get the `did:key` for an agent
+pub fn did_key(agent: &AgentPubKey) -> String {
+    let mut bytes = ED25519_PUB_MULTICODEC.to_vec();
+    bytes.extend( agent.get_raw_32() );
+
+    format!("did:key:z{}", encoding::base58btc_encode( &bytes ) )
+}
+
+
+/// Get the agent for a `did:key` made by [`did_key`]
+pub fn agent_from_did_key(did: &str) -> ExternResult<AgentPubKey> {
+    let bytes = did.strip_prefix("did:key:z")
+        .and_then( encoding::base58btc_decode )
+        .ok_or(guest_error!(format!("Invalid did:key '{}'", did )))?;
+
+    if bytes.len() != 34 || bytes[..2] != ED25519_PUB_MULTICODEC {
+        return Err(guest_error!(format!("did:key '{}' is not an Ed25519 key", did )));
+    }
+
+    Ok( AgentPubKey::from_raw_32( bytes[2..].to_vec() ) )
+}
+
+
+fn hash_from_hex(text: &str) -> ExternResult<MerkleHash> {
+    MerkleHash::from_hex( text )
+        .map_err(|err| guest_error!(format!("Invalid hash '{}': {}", text, err )))
+}
+
+
+/// A revealed leaf and its Merkle proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleDisclosure {
+    /// The label of the revealed leaf
+    pub label: String,
+    /// Hex encoded msgpack of the leaf value
+    #[serde(rename = "encodedValue")]
+    pub encoded_value: String,
+    /// Hex encoded salt of the leaf
+    pub salt: String,
+    /// The leaf's index in the Merkle tree
+    pub index: u64,
+    /// Hex encoded hash of the leaf
+    pub leaf: String,
+    /// Hex encoded Merkle proof hash list
+    pub proof: Vec<String>,
+}
+
+/// The proof section of a [`VerifiableCredential`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleDisclosureProof {
+    /// Always [`MERKLE_DISCLOSURE_PROOF_TYPE`]
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    /// Hex encoded Merkle root
+    #[serde(rename = "merkleRoot")]
+    pub merkle_root: String,
+    /// The total number of leaves in the Merkle tree
+    #[serde(rename = "totalLeaves")]
+    pub total_leaves: u64,
+    /// The revealed leaves
+    pub disclosures: Vec<MerkleDisclosure>,
+}
+
+/// A credential for the labels revealed from one tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifiableCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub credential_type: Vec<String>,
+    pub issuer: String,
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: BTreeMap<String, rmpv::Value>,
+    pub proof: MerkleDisclosureProof,
+}
+
+/// A W3C Verifiable Presentation of Merkle disclosures
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifiablePresentation {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub presentation_type: Vec<String>,
+    pub holder: String,
+    #[serde(rename = "verifiableCredential")]
+    pub verifiable_credential: Vec<VerifiableCredential>,
+}
+
+impl VerifiablePresentation {
+    /// Build a presentation from a holder's proofs
+    pub fn from_presentation(presentation: &PresentationPayload) -> ExternResult<Self> {
+        let holder = did_key( &presentation.holder );
+        let mut credential_subject = BTreeMap::new();
+        let mut disclosures = Vec::new();
+
+        credential_subject.insert( "id".to_string(), rmpv::Value::from( holder.clone() ) );
+
+        for proof in presentation.proofs.iter() {
+            // The proof type has no field for the hash function
+            if !proof.hash_algorithm.is_sha256() {
+                return Err(guest_error!(format!("Proof for '{}' is from a {:?} tree; presentations only support {:?}", proof.target.label, proof.hash_algorithm, HashAlgorithm::Sha256 )));
+            }
+
+            let encoded_value = rmp_serde::to_vec( &proof.target.value )
+                .map_err(|err| guest_error!(format!("Failed to encode value of '{}': {:?}", proof.target.label, err )))?;
+
+            credential_subject.insert( proof.target.label.clone(), proof.target.value.clone() );
+            disclosures.push( MerkleDisclosure {
+                label: proof.target.label.clone(),
+                encoded_value: hex::encode( encoded_value ),
+                salt: hex::encode( &proof.target.salt ),
+                index: proof.index,
+                leaf: proof.leaf.to_hex(),
+                proof: proof.proof.iter().map( MerkleHash::to_hex ).collect(),
+            });
+        }
+
+        let total_leaves = presentation.proofs.first()
+            .map(|proof| proof.total_leaves )
+            .unwrap_or(0);
+
+        Ok(
+            VerifiablePresentation {
+                context: vec![ VC_CONTEXT.to_string() ],
+                presentation_type: vec![ "VerifiablePresentation".to_string() ],
+                holder: holder.clone(),
+                verifiable_credential: vec![
+                    VerifiableCredential {
+                        context: vec![ VC_CONTEXT.to_string() ],
+                        credential_type: vec![
+                            "VerifiableCredential".to_string(),
+                            "MerkleDisclosureCredential".to_string(),
+                        ],
+                        issuer: holder,
+                        credential_subject,
+                        proof: MerkleDisclosureProof {
+                            proof_type: MERKLE_DISCLOSURE_PROOF_TYPE.to_string(),
+                            merkle_root: hex::encode( presentation.root ),
+                            total_leaves,
+                            disclosures,
+                        },
+                    },
+                ],
+            }
+        )
+    }
+
+    /// Get the holder's agent pubkey
+    pub fn holder_agent(&self) -> ExternResult<AgentPubKey> {
+        agent_from_did_key( &self.holder )
+    }
+
+    /// Rebuild the leaf proofs of every credential so they can be verified
+    pub fn leaf_proofs(&self) -> ExternResult<Vec<LeafProofPayload>> {
+        let mut leaf_proofs = Vec::new();
+
+        for credential in self.verifiable_credential.iter() {
+            leaf_proofs.extend( credential.leaf_proofs()? );
+        }
+
+        Ok( leaf_proofs )
+    }
+
+    /// Verify every disclosure in this presentation and that each `credentialSubject` matches them
+    ///
+    /// A presentation without any disclosures is not valid.
+    pub fn verify(&self) -> ExternResult<bool> {
+        if self.verifiable_credential.is_empty() {
+            return Ok( false );
+        }
+
+        for credential in self.verifiable_credential.iter() {
+            let leaf_proofs = credential.leaf_proofs()?;
+
+            if leaf_proofs.is_empty() || !credential.subject_matches( &self.holder, &leaf_proofs ) {
+                return Ok( false );
+            }
+
+            for leaf_proof in leaf_proofs {
+                if !leaf_proof.verify()? {
+                    return Ok( false );
+                }
+            }
+        }
+
+        Ok( true )
+    }
+}
+
+impl VerifiableCredential {
+    /// Rebuild the leaf proofs of this credential's disclosures
+    pub fn leaf_proofs(&self) -> ExternResult<Vec<LeafProofPayload>> {
+        let proof = &self.proof;
+
+        if proof.proof_type != MERKLE_DISCLOSURE_PROOF_TYPE {
+            return Err(guest_error!(format!("Unsupported proof type '{}'", proof.proof_type )));
+        }
+
+        let root = hash_from_hex( &proof.merkle_root )?;
+
+        proof.disclosures.iter()
+            .map(|disclosure| {
+                let encoded_value = hex::decode( &disclosure.encoded_value )
+                    .map_err(|err| guest_error!(format!("Invalid value for '{}': {}", disclosure.label, err )))?;
+
+                Ok( LeafProofPayload {
+                    proof: disclosure.proof.iter()
+                        .map(|hash| hash_from_hex( hash ) )
+                        .collect::<ExternResult<Vec<MerkleHash>>>()?,
+                    index: disclosure.index,
+                    target: LeafDataBlock {
+                        label: disclosure.label.clone(),
+                        value: rmp_serde::from_slice( &encoded_value )
+                            .map_err(|err| guest_error!(format!("Invalid value for '{}': {:?}", disclosure.label, err )))?,
+                        salt: hex::decode( &disclosure.salt )
+                            .map_err(|err| guest_error!(format!("Invalid salt for '{}': {}", disclosure.label, err )))?,
+                    },
+                    leaf: hash_from_hex( &disclosure.leaf )?,
+                    root,
+                    total_leaves: proof.total_leaves,
+                    hash_algorithm: HashAlgorithm::Sha256,
+                })
+            })
+            .collect()
+    }
+
+    /// Check that `credentialSubject` is the holder's `id` plus exactly the disclosed values
+    fn subject_matches(&self, holder: &str, leaf_proofs: &[LeafProofPayload]) -> bool {
+        if self.credential_subject.get("id") != Some( &rmpv::Value::from( holder ) )
+            || self.credential_subject.len() != leaf_proofs.len() + 1
+        {
+            return false;
+        }
+
+        leaf_proofs.iter()
+            .all(|leaf_proof| {
+                leaf_proof.target.label != "id"
+                    && self.credential_subject.get( &leaf_proof.target.label ) == Some( &leaf_proof.target.value )
+            })
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify;
+
+    fn presentation() -> PresentationPayload {
+        let blocks : Vec<LeafDataBlock> = [ ("name", rmpv::Value::from("Alice")), ("age", rmpv::Value::from( 42 )) ]
+            .iter()
+            .enumerate()
+            .map(|(index, (label, value))| LeafDataBlock {
+                label: label.to_string(),
+                value: value.clone(),
+                salt: verify::derive_salt( b"entropy", index ).unwrap(),
+            })
+            .collect();
+        let leaves : Vec<[u8; 32]> = blocks.iter()
+            .map(|block| block.hash().unwrap() )
+            .collect();
+        let root = verify::calculate_root( &leaves ).unwrap();
+
+        PresentationPayload {
+            request_id: ActionHash::from_raw_36( vec![ 0; 36 ] ),
+            holder: AgentPubKey::from_raw_32( vec![ 1; 32 ] ),
+            root,
+            proofs: blocks.iter().enumerate()
+                .map(|(index, block)| LeafProofPayload {
+                    proof: MerkleHash::from_raw_vec( HashAlgorithm::Sha256.proof( &leaves, index ).unwrap() ),
+                    index: index as u64,
+                    target: block.clone(),
+                    leaf: leaves[ index ].into(),
+                    root: root.into(),
+                    total_leaves: leaves.len() as u64,
+                    hash_algorithm: HashAlgorithm::Sha256,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let vp = VerifiablePresentation::from_presentation( &presentation() ).unwrap();
+
+        assert!( vp.verify().unwrap() );
+
+        // The displayed claims must match the disclosures
+        let mut forged = vp.clone();
+        forged.verifiable_credential[0].credential_subject.insert( "age".to_string(), rmpv::Value::from( 21 ) );
+        assert!( !forged.verify().unwrap() );
+
+        let mut extra = vp.clone();
+        extra.verifiable_credential[0].credential_subject.insert( "admin".to_string(), rmpv::Value::from( true ) );
+        assert!( !extra.verify().unwrap() );
+
+        let mut empty = vp.clone();
+        empty.verifiable_credential[0].proof.disclosures.clear();
+        empty.verifiable_credential[0].credential_subject.retain(|label, _| label == "id" );
+        assert!( !empty.verify().unwrap() );
+
+        let mut no_credentials = vp;
+        no_credentials.verifiable_credential.clear();
+        assert!( !no_credentials.verify().unwrap() );
+    }
+}
@@ -0,0 +1,124 @@
+import { Logger }			from '@whi/weblogger';
+const log				= new Logger("test-js-verifier", process.env.LOG_LEVEL );
+
+import fs				from 'node:fs';
+import path				from 'path';
+import { expect }			from 'chai';
+import msgpack				from '@msgpack/msgpack';
+import {
+    initSync,
+    verifyPayload,
+    hashLeaf,
+    deriveSalt,
+    calculateRoot,
+    proofRoot,
+    verifyLeafProof,
+}					from '../../merklicious_sdk/pkg/merklicious_sdk.js';
+
+const __filename			= new URL(import.meta.url).pathname;
+const __dirname				= path.dirname( __filename );
+const PKG_WASM_PATH			= path.join( __dirname, "../../merklicious_sdk/pkg/merklicious_sdk_bg.wasm" );
+const VECTORS_PATH			= path.join( __dirname, "../vectors/merklicious_v1.json" );
+const ETHEREUM_VECTORS_PATH		= path.join( __dirname, "../vectors/ethereum_standard_merkle_tree.json" );
+
+const vectors				= JSON.parse( fs.readFileSync( VECTORS_PATH, "utf8" ) );
+const ethereum_vectors			= JSON.parse( fs.readFileSync( ETHEREUM_VECTORS_PATH, "utf8" ) );
+
+function bytes ( hex ) {
+    return new Uint8Array( Buffer.from( hex.replace(/^0x/, ""), "hex" ) );
+}
+
+function hex ( bytes ) {
+    return Buffer.from( bytes ).toString("hex");
+}
+
+
+function vector_tests () {
+
+    it("should derive salts and hash leaves", async function () {
+	for ( let vector of vectors.cases ) {
+	    const entropy		= bytes( vector.entropy );
+
+	    vector.leaves.forEach( (leaf, index) => {
+		const salt		= deriveSalt( entropy, index );
+
+		expect( hex( salt )					).to.equal( leaf.salt, `${vector.name}: salt of '${leaf.label}'` );
+		expect( hex( hashLeaf( leaf.label, leaf.value, salt ) )	).to.equal( leaf.leaf, `${vector.name}: leaf '${leaf.label}'` );
+	    });
+	}
+    });
+
+    it("should calculate roots", async function () {
+	for ( let vector of vectors.cases ) {
+	    const leaves		= [ ...vector.leaves.map( leaf => leaf.leaf ), ...(vector.padding_leaves || []) ];
+
+	    expect( hex( calculateRoot( leaves.map( bytes ) ) )	).to.equal( vector.root, vector.name );
+	}
+    });
+
+    it("should verify proofs", async function () {
+	for ( let vector of vectors.cases ) {
+	    const total_leaves		= vector.leaves.length + (vector.padding_leaves || []).length;
+	    const root			= bytes( vector.root );
+
+	    vector.leaves.forEach( (leaf, index) => {
+		const proof		= vector.proofs[ index ].map( bytes );
+
+		expect( hex( proofRoot( index, bytes( leaf.leaf ), proof, total_leaves ) )	).to.equal( vector.root, `${vector.name}: proof ${index}` );
+		expect( verifyLeafProof( root, index, bytes( leaf.leaf ), proof, total_leaves )	).to.be.true;
+
+		const payload		= {
+		    "proof":		proof,
+		    "index":		index,
+		    "target": {
+			"label":	leaf.label,
+			"value":	leaf.value,
+			"salt":		bytes( leaf.salt ),
+		    },
+		    "leaf":		bytes( leaf.leaf ),
+		    "root":		root,
+		    "total_leaves":	total_leaves,
+		};
+
+		expect( verifyPayload( payload )						).to.be.true;
+		expect( verifyPayload({ ...payload, "target": { ...payload.target, "value": "forged" } }) ).to.be.false;
+	    });
+	}
+    });
+
+    it("should hash Keccak-256 leaves and roots", async function () {
+	for ( let vector of ethereum_vectors ) {
+	    const { tree, values }	= vector.dump;
+	    const leaves		= values.map( ({ value, treeIndex }) => {
+		const [ label, encoded, salt ]	= value;
+		const leaf			= hashLeaf( label, msgpack.decode( bytes( encoded ) ), bytes( salt ), "Keccak256" );
+
+		expect( "0x" + hex( leaf )	).to.equal( tree[ treeIndex ] );
+
+		return leaf;
+	    });
+
+	    expect( "0x" + hex( calculateRoot( leaves, "Keccak256" ) )	).to.equal( vector.root );
+
+	    leaves.forEach( (leaf, index) => {
+		const proof		= vector.proofs[ index ].map( bytes );
+
+		expect( verifyLeafProof( bytes( vector.root ), index, leaf, proof, leaves.length, "Keccak256" )	).to.be.true;
+	    });
+	}
+    });
+
+}
+
+
+describe("JS Verifier", () => {
+
+    before(async function () {
+	initSync({ "module": fs.readFileSync( PKG_WASM_PATH ) });
+
+	log.info("Loaded %s vectors and %s Ethereum vectors", vectors.cases.length, ethereum_vectors.length );
+    });
+
+    describe("Vectors", vector_tests );
+
+});
@@ -127,6 +127,801 @@ function basic_tests () {
 	expect( verify			).to.be.true;
     });
 
+    it("should create padded tree", async function () {
+	let leaves			= flatten_data( drivers_license );
+	const tree_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": leaves,
+	    "padding": { "Size": 64 },
+	}) );
+	const padded_tree		= intoStruct( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr ), TreeStruct );
+
+	expect( padded_tree.leaves	).to.have.length( 64 );
+
+	const result			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": tree_addr,
+	    "label": "date_of_birth",
+	});
+
+	expect( result.total_leaves	).to.equal( 64 );
+
+	const verify			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_leaf_proof", {
+	    "proof": result.proof,
+	    "index": result.index,
+	    "leaf": result.leaf,
+	    "root": result.root,
+	    "total_leaves": result.total_leaves,
+	});
+
+	expect( verify			).to.be.true;
+    });
+
+    it("should get proof from stored layers", async function () {
+	let leaves			= flatten_data( drivers_license );
+	const tree_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": leaves,
+	    "padding": "PowerOfTwo",
+	    "store_layers": true,
+	}) );
+	const layered_tree		= intoStruct( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr ), TreeStruct );
+
+	expect( layered_tree.layers	).to.be.a("ActionHash");
+
+	for ( let label of [ "id", "date_of_birth", "organ_donor" ] ) {
+	    const result		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+		"tree_id": tree_addr,
+		label,
+	    });
+
+	    const verify		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_leaf_proof", {
+		"proof": result.proof,
+		"index": result.index,
+		"leaf": result.leaf,
+		"root": result.root,
+		"total_leaves": result.total_leaves,
+	    });
+
+	    expect( verify		).to.be.true;
+	}
+    });
+
+    it("should create chunked tree", async function () {
+	let leaves			= flatten_data( drivers_license );
+	const tree_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": leaves,
+	    "padding": { "Size": 30 },
+	    "chunk_size": 4,
+	}) );
+	const chunked_tree		= intoStruct( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr ), TreeStruct );
+
+	expect( chunked_tree.data_blocks	).to.be.null;
+	expect( chunked_tree.leaves		).to.have.length( 0 );
+	expect( chunked_tree.chunks		).to.have.length( 8 );
+
+	for ( let label of [ "id", "date_of_birth", "organ_donor" ] ) {
+	    const result		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+		"tree_id": tree_addr,
+		label,
+	    });
+
+	    expect( result.total_leaves	).to.equal( 30 );
+
+	    const verify		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_leaf_proof", {
+		"proof": result.proof,
+		"index": result.index,
+		"leaf": result.leaf,
+		"root": result.root,
+		"total_leaves": result.total_leaves,
+	    });
+
+	    expect( verify		).to.be.true;
+	}
+    });
+
+    it("should create sparse tree and get inclusion/exclusion proofs", async function () {
+	let leaves			= flatten_data( drivers_license );
+	const tree_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": leaves,
+	    "tree_type": "Sparse",
+	}) );
+	const sparse_tree		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr );
+
+	expect( sparse_tree.tree_type	).to.equal( "Sparse" );
+
+	const inclusion			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_sparse_proof", {
+	    "tree_id": tree_addr,
+	    "label": "date_of_birth",
+	});
+	log.debug("Sparse inclusion proof: %s", json.debug(inclusion) );
+
+	expect( inclusion.target.label	).to.equal( "date_of_birth" );
+	expect( inclusion		).to.not.have.any.keys( "index", "total_leaves" );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_sparse_proof", {
+	    "proof": inclusion.proof,
+	    "label": inclusion.label,
+	    "leaf": inclusion.leaf,
+	    "root": inclusion.root,
+	})				).to.be.true;
+
+	const exclusion			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_sparse_proof", {
+	    "tree_id": tree_addr,
+	    "label": "criminal_record",
+	});
+
+	expect( exclusion.target	).to.be.null;
+	expect( exclusion.leaf		).to.be.null;
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_sparse_proof", {
+	    "proof": exclusion.proof,
+	    "label": exclusion.label,
+	    "leaf": null,
+	    "root": exclusion.root,
+	})				).to.be.true;
+
+	// An exclusion proof cannot be used to claim inclusion
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_sparse_proof", {
+	    "proof": exclusion.proof,
+	    "label": "date_of_birth",
+	    "leaf": inclusion.leaf,
+	    "root": exclusion.root,
+	})				).to.be.false;
+    });
+
+    it("should append to log and get consistency proof", async function () {
+	const log_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_log", {} ) );
+
+	await clients.alice.call( DNA_NAME, MAIN_ZOME, "append_leaves", {
+	    "log_id": log_addr,
+	    "leaves": flatten_data( drivers_license.name ),
+	});
+	const old_state			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_log_state", log_addr );
+
+	expect( old_state.size		).to.equal( 2 );
+
+	await clients.alice.call( DNA_NAME, MAIN_ZOME, "append_leaves", {
+	    "log_id": log_addr,
+	    "leaves": flatten_data( drivers_license.address ),
+	});
+	const new_state			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_log_state", log_addr );
+
+	expect( new_state.size		).to.equal( 7 );
+
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "append_leaves", {
+		"log_id": log_addr,
+		"leaves": [],
+	    });
+	}, "empty batch of leaves" );
+
+	const consistency		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_consistency_proof", {
+	    "log_id": log_addr,
+	    "old_size": old_state.size,
+	    "new_size": new_state.size,
+	});
+	log.debug("Consistency proof: %s", json.debug(consistency) );
+
+	expect( consistency.old_root	).to.deep.equal( old_state.root );
+	expect( consistency.new_root	).to.deep.equal( new_state.root );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_consistency_proof", consistency ) ).to.be.true;
+
+	// A proof against a different old root must fail
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_consistency_proof", {
+	    ...consistency,
+	    "old_root": consistency.new_root,
+	})				).to.be.false;
+
+	const leaf_proof		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_log_leaf_proof", {
+	    "log_id": log_addr,
+	    "index": 1,
+	    "size": old_state.size,
+	});
+
+	expect( leaf_proof.target.label	).to.equal( "last" );
+	expect( leaf_proof.root		).to.deep.equal( old_state.root );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_log_leaf_proof", leaf_proof ) ).to.be.true;
+    });
+
+    it("should append to MMR and get inclusion proof", async function () {
+	const mmr_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_mmr", {} ) );
+	const leaves			= flatten_data( drivers_license.address );
+
+	for ( let leaf of leaves ) {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "append_mmr_leaf", {
+		"mmr_id": mmr_addr,
+		leaf,
+	    });
+	}
+
+	const state			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_mmr_state", mmr_addr );
+
+	expect( state.size		).to.equal( 5 );
+	expect( state.peaks		).to.have.length( 2 );
+
+	const proof			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_mmr_proof", {
+	    "mmr_id": mmr_addr,
+	    "index": 2,
+	});
+	log.debug("MMR proof: %s", json.debug(proof) );
+
+	expect( proof.target.label	).to.equal( "province" );
+	expect( proof.siblings		).to.have.length( 2 );
+	expect( proof.root		).to.deep.equal( state.root );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_mmr_proof", proof ) ).to.be.true;
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_mmr_proof", {
+	    ...proof,
+	    "index": 3,
+	})				).to.be.false;
+    });
+
+    it("should aggregate trees and get chained proof", async function () {
+	const bobby_tree_addr		= new ActionHash( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": flatten_data( drivers_license ),
+	}) );
+	const bobby_tree		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_tree", bobby_tree_addr );
+
+	const aggregate_addr		= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_aggregate_tree", {
+	    "trees": [
+		{ "TreeId": t1_addr },
+		{ "Root": bobby_tree.root },
+	    ],
+	}) );
+	const aggregate			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_aggregate_tree", aggregate_addr );
+
+	expect( aggregate.roots		).to.have.length( 2 );
+
+	const proof			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_aggregate_leaf_proof", {
+	    "aggregate_id": aggregate_addr,
+	    "tree_id": t1_addr,
+	    "label": "date_of_birth",
+	});
+	log.debug("Aggregate proof: %s", json.debug(proof) );
+
+	expect( proof.tree_index	).to.equal( 0 );
+	expect( proof.root		).to.deep.equal( aggregate.root );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_aggregate_leaf_proof", proof ) ).to.be.true;
+
+	// Chain a proof from a sub-tree owned by another agent
+	const bobby_leaf_proof		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": bobby_tree_addr,
+	    "label": "sex",
+	});
+	const chained			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "chain_leaf_proof", {
+	    "aggregate_id": aggregate_addr,
+	    "leaf_proof": bobby_leaf_proof,
+	});
+
+	expect( chained.tree_index	).to.equal( 1 );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_aggregate_leaf_proof", chained ) ).to.be.true;
+
+	// A sub-tree proof cannot be moved to a different position in the aggregate
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_aggregate_leaf_proof", {
+	    ...chained,
+	    "tree_index": 0,
+	})				).to.be.false;
+    });
+
+    it("should anchor roots with a witness", async function () {
+	const witness			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	const t1			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", t1_addr );
+	const other_root		= [ ...crypto.randomBytes( 32 ) ];
+	const requester			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+
+	const cap_secret		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "allow_anchor_requests", [
+	    requester.agent_initial_pubkey,
+	]);
+	const anchor_addr		= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "request_anchor", {
+	    "witness": witness.agent_initial_pubkey,
+	    "cap_secret": cap_secret,
+	    "roots": [ other_root, t1.root ],
+	}) );
+	log.debug("Anchor ID: %s", anchor_addr );
+
+	const anchors			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_anchors_for_root", t1.root );
+
+	expect( anchors.map( addr => String(new ActionHash(addr)) ) ).to.include( String(anchor_addr) );
+
+	const proof			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_anchor_proof", {
+	    "root": t1.root,
+	});
+	log.debug("Anchor proof: %s", json.debug(proof) );
+
+	expect( String(new ActionHash(proof.anchor_id))	).to.equal( String(anchor_addr) );
+	expect( String(new AgentPubKey(proof.witness))	).to.equal( String(new AgentPubKey(witness.agent_initial_pubkey)) );
+	expect( proof.index		).to.equal( 1 );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_anchor_proof", proof ) ).to.be.true;
+
+	// Claiming a different time must fail
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_anchor_proof", {
+	    ...proof,
+	    "anchored_at": proof.anchored_at - 1,
+	})				).to.be.false;
+    });
+
+    it("should attest a holder's root as an issuer", async function () {
+	const holder			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	const issuer			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	const t1			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", t1_addr );
+
+	await clients.bobby.call( DNA_NAME, MAIN_ZOME, "attest_root", {
+	    "holder": holder.agent_initial_pubkey,
+	    "root": t1.root,
+	});
+
+	const attestations		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_attestations_for_root", t1.root );
+	log.debug("Attestations: %s", json.debug(attestations) );
+
+	expect( attestations		).to.have.length( 1 );
+	expect( String(new AgentPubKey(attestations[0].issuer)) ).to.equal( String(new AgentPubKey(issuer.agent_initial_pubkey)) );
+
+	const leaf_proof		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": t1_addr,
+	    "label": "date_of_birth",
+	});
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_attested_leaf_proof", {
+	    leaf_proof,
+	    "issuer": issuer.agent_initial_pubkey,
+	})				).to.be.true;
+
+	// The holder has not attested their own root
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_attested_leaf_proof", {
+	    leaf_proof,
+	    "issuer": holder.agent_initial_pubkey,
+	})				).to.be.false;
+    });
+
+    it("should request and issue a credential", async function () {
+	const holder			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	const issuer			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami" );
+
+	const request_addr		= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "request_credential", {
+	    "issuer": issuer.agent_initial_pubkey,
+	    "leaves": flatten_data( drivers_license ),
+	}) );
+
+	const requests			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_credential_requests" );
+
+	expect( requests		).to.have.length( 1 );
+	expect( String(new ActionHash(requests[0].request_id)) ).to.equal( String(request_addr) );
+	expect( String(new AgentPubKey(requests[0].request.holder)) ).to.equal( String(new AgentPubKey(holder.agent_initial_pubkey)) );
+
+	const issuance			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "issue_credential", request_addr );
+	log.debug("Credential issuance: %s", json.debug(issuance) );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_credential_requests" ) ).to.have.length( 0 );
+
+	const holder_tree		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", issuance.holder_tree_id );
+
+	expect( holder_tree.root	).to.deep.equal( issuance.root );
+
+	// The request was answered, so it can't be issued again
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "issue_credential", request_addr );
+	}, "is not pending" );
+
+	await delay( 1_000 );
+
+	const leaf_proof		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": issuance.holder_tree_id,
+	    "label": "date_of_birth",
+	});
+
+	expect( await clients.alice.call( DNA_NAME, MAIN_ZOME, "verify_attested_leaf_proof", {
+	    leaf_proof,
+	    "issuer": issuer.agent_initial_pubkey,
+	})				).to.be.true;
+    });
+
+    it("should request, approve and deny proofs", async function () {
+	const holder			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+
+	const request_addr		= new ActionHash( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "request_proof", {
+	    "holder": holder.agent_initial_pubkey,
+	    "labels": [ "date_of_birth", "organ_donor" ],
+	}) );
+
+	const requests			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_proof_requests" );
+
+	expect( requests		).to.have.length( 1 );
+	expect( requests[0].request.labels ).to.deep.equal( [ "date_of_birth", "organ_donor" ] );
+
+	const presentation		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "approve_proof_request", {
+	    "request_id": request_addr,
+	    "tree_id": t1_addr,
+	});
+	log.debug("Presentation: %s", json.debug(presentation) );
+
+	expect( presentation.proofs	).to.have.length( 2 );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_presentation", presentation ) ).to.be.true;
+
+	const vp			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "export_verifiable_presentation", presentation );
+	log.debug("Verifiable presentation: %s", JSON.stringify( vp, null, 4 ) );
+
+	expect( vp["@context"]		).to.deep.equal( [ "https://www.w3.org/2018/credentials/v1" ] );
+	expect( vp.holder		).to.match( /^did:key:z6Mk/ );
+	expect( vp.verifiableCredential[0].proof.disclosures ).to.have.length( 2 );
+
+	expect( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_verifiable_presentation", vp ) ).to.be.true;
+
+	const denied_addr		= new ActionHash( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "request_proof", {
+	    "holder": holder.agent_initial_pubkey,
+	    "labels": [ "address.street" ],
+	}) );
+
+	await clients.alice.call( DNA_NAME, MAIN_ZOME, "deny_proof_request", denied_addr );
+
+	expect( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_proof_requests" ) ).to.have.length( 0 );
+
+	// Answered requests can't be answered again
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "approve_proof_request", {
+		"request_id": denied_addr,
+		"tree_id": t1_addr,
+	    });
+	}, "is not pending" );
+    });
+
+    it("should record disclosures", async function () {
+	const verifier			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami" );
+
+	await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": t1_addr,
+	    "label": "sex",
+	    "recipient": verifier.agent_initial_pubkey,
+	});
+
+	const history			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_disclosure_history", {
+	    "tree_id": t1_addr,
+	    "recipient": verifier.agent_initial_pubkey,
+	});
+	log.debug("Disclosure history: %s", json.debug(history) );
+
+	// The approved presentation from the previous test plus the proof above
+	expect( history			).to.have.length( 2 );
+	expect( history[0].labels	).to.deep.equal( [ "date_of_birth", "organ_donor" ] );
+	expect( history[1].labels	).to.deep.equal( [ "sex" ] );
+
+	const others			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_disclosure_history", {
+	    "recipient": (await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" )).agent_initial_pubkey,
+	});
+
+	expect( others			).to.have.length( 0 );
+    });
+
+    it("should delegate proof generation", async function () {
+	const author			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	const delegate			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami" );
+
+	const grant			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "grant_proof_delegation", {
+	    "delegate": delegate.agent_initial_pubkey,
+	    "tree_id": t1_addr,
+	    "labels": [ "organ_donor" ],
+	});
+	log.debug("Delegation: %s", json.debug(grant) );
+
+	const input			= {
+	    "author": author.agent_initial_pubkey,
+	    "cap_secret": grant.cap_secret,
+	    "tree_id": t1_addr,
+	};
+	const leaf_proof		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_delegated_leaf_proof", {
+	    ...input,
+	    "label": "organ_donor",
+	});
+
+	expect( leaf_proof.target.label	).to.equal( "organ_donor" );
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_delegated_leaf_proof", {
+		...input,
+		"label": "date_of_birth",
+	    });
+	}, "outside the scope delegated" );
+
+	const history			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_disclosure_history", {
+	    "recipient": delegate.agent_initial_pubkey,
+	});
+
+	expect( history[ history.length - 1 ].labels ).to.deep.equal( [ "organ_donor" ] );
+
+	await clients.alice.call( DNA_NAME, MAIN_ZOME, "revoke_proof_delegation", grant.delegation_id );
+
+	expect( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_proof_delegations" ) ).to.have.length( 0 );
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_delegated_leaf_proof", {
+		...input,
+		"label": "organ_donor",
+	    });
+	}, "Unauthorized" );
+    });
+
+    it("should back up a tree for a recovery device", async function () {
+	const author			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami" );
+	// Every agent in this test shares one keystore, so the recovery device is simulated with a key
+	// made outside of it
+	const { publicKey }		= crypto.generateKeyPairSync("x25519");
+	const recovery_key		= Buffer.from( publicKey.export({ "format": "jwk" }).x, "base64url" );
+
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "backup_tree", {
+		"tree_id": t1_addr,
+		"recovery_key": await clients.bobby.call( DNA_NAME, MAIN_ZOME, "create_recovery_key" ),
+	    });
+	}, "this device's keystore" );
+
+	const backup_addr		= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "backup_tree", {
+	    "tree_id": t1_addr,
+	    recovery_key,
+	}) );
+	log.debug("Backup ID: %s", backup_addr );
+
+	const backups			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree_backups", author.agent_initial_pubkey );
+
+	expect( backups.map( addr => String(new ActionHash(addr)) ) ).to.include( String(backup_addr) );
+
+	const backup			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_tree_backup", backup_addr );
+
+	expect( backup.root		).to.be.undefined;
+	expect( Buffer.from( backup.recipient ) ).to.deep.equal( recovery_key );
+	expect( JSON.stringify( backup ) ).to.not.include( "Canada" );
+
+	// Only the recovery device can decrypt the backup
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "recover_tree", backup_addr );
+	}, "Couldn't decrypt backup" );
+    });
+
+    it("should export and import a tree", async function () {
+	const bundle			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "export_tree", t1_addr );
+	log.debug("Tree bundle: %s", json.debug(bundle) );
+
+	expect( bundle.version		).to.equal( 1 );
+	expect( bundle.hash_algorithm	).to.equal( "Sha256" );
+
+	const imported_addr		= new ActionHash( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "import_tree", bundle ) );
+	const imported			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_tree", imported_addr );
+
+	expect( imported.root		).to.deep.equal( bundle.root );
+
+	const tampered			= {
+	    ...bundle,
+	    "data_blocks": bundle.data_blocks.map( block => {
+		return block.label === "sex" ? { ...block, "value": "female" } : block;
+	    }),
+	};
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "import_tree", tampered );
+	}, "do not match" );
+    });
+
+    it("should issue, present and verify an SD-JWT", async function () {
+	const sd_jwt			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "issue_sd_jwt", t1_addr );
+	log.debug("SD-JWT: %s", json.debug(sd_jwt) );
+
+	expect( sd_jwt.jwt.split(".")	).to.have.length( 3 );
+
+	const presentation		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "present_sd_jwt", {
+	    sd_jwt,
+	    "labels": [ "date_of_birth" ],
+	});
+	log.debug("SD-JWT presentation: %s", presentation );
+
+	const verified			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_sd_jwt", presentation );
+
+	expect( verified.claims.iss	).to.match( /^did:key:z6Mk/ );
+	expect( verified.claims._sd	).to.have.length( sd_jwt.disclosures.length );
+	expect( Object.keys( verified.disclosed ) ).to.deep.equal( [ "date_of_birth" ] );
+
+	const forged			= Buffer.from( JSON.stringify([ "c2FsdA", "sex", "female" ]) ).toString("base64url");
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_sd_jwt", `${presentation}${forged}~` );
+	}, "not part of the SD-JWT" );
+    });
+
+    it("should encode proofs for transport", async function () {
+	const result			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": t1_addr,
+	    "label": "date_of_birth",
+	});
+
+	const encoded			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "encode_leaf_proof", result );
+	log.debug("Encoded leaf proof (%s chars): %s", encoded.length, encoded );
+
+	expect( encoded			).to.match( /^u[A-Za-z0-9_-]+$/ );
+
+	const decoded			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_leaf_proof", encoded );
+
+	expect( decoded			).to.deep.equal( result );
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_leaf_proof", "xAQ" );
+	}, "Unsupported multibase prefix" );
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_leaf_proof", "fff" );
+	}, "Unsupported transport version" );
+    });
+
+    it("should encode proofs for QR codes", async function () {
+	const result			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": t1_addr,
+	    "label": "date_of_birth",
+	});
+
+	const compact			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "encode_compact_leaf_proof", {
+	    "proof": result,
+	});
+	log.debug("Compact leaf proof: %s bytes", compact.length );
+
+	expect( compact.length		).to.be.below( msgpack.encode( result ).length );
+
+	const decoded			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_compact_leaf_proof", {
+	    "bytes": compact,
+	});
+
+	expect( decoded			).to.deep.equal( result );
+
+	const truncated			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "encode_compact_leaf_proof", {
+	    "proof": result,
+	    "root_prefix": 8,
+	});
+
+	expect( truncated.length	).to.equal( compact.length - 23 );
+
+	// A truncated root is only accepted against a root the verifier already knows
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_compact_leaf_proof", {
+		"bytes": truncated,
+	    });
+	}, "expected root is required" );
+
+	const rebuilt			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_compact_leaf_proof", {
+	    "bytes": truncated,
+	    "expected_root": result.root,
+	});
+
+	expect( rebuilt.root		).to.deep.equal( result.root );
+
+	await expect_reject( async () => {
+	    await clients.bobby.call( DNA_NAME, MAIN_ZOME, "decode_compact_leaf_proof", {
+		"bytes": truncated,
+		"expected_root": [ ...crypto.randomBytes( 32 ) ],
+	    });
+	}, "expected root" );
+
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "encode_compact_leaf_proof", {
+		"proof": result,
+		"budget": 100,
+	    });
+	}, "over the budget" );
+    });
+
+    it("should export Ethereum compatible proofs", async function () {
+	const dump			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "export_ethereum_tree", t1_addr );
+	log.debug("Ethereum tree: %s", json.debug( dump ) );
+
+	expect( dump.format		).to.equal("standard-v1");
+	expect( dump.leafEncoding	).to.deep.equal([ "string", "bytes", "bytes" ]);
+	expect( dump.tree		).to.have.length( dump.values.length * 2 - 1 );
+
+	const result			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_ethereum_leaf_proof", {
+	    "tree_id": t1_addr,
+	    "label": "date_of_birth",
+	});
+
+	expect( result.root		).to.equal( dump.tree[0] );
+	expect( result.leaf		).to.match( /^0x[0-9a-f]{64}$/ );
+
+	const valid			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_ethereum_leaf_proof", result );
+
+	expect( valid			).to.be.true;
+
+	const forged			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_ethereum_leaf_proof", {
+	    ...result,
+	    "target": {
+		...result.target,
+		"value": 0,
+	    },
+	});
+
+	expect( forged			).to.be.false;
+    });
+
+    it("should create Keccak-256 tree", async function () {
+	const tree_addr			= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+	    "leaves": flatten_data( drivers_license ),
+	    "hash_algorithm": "Keccak256",
+	}) );
+	const keccak_tree		= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr );
+	const dump			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "export_ethereum_tree", tree_addr );
+
+	expect( keccak_tree.hash_algorithm	).to.equal("Keccak256");
+	expect( "0x" + Buffer.from( keccak_tree.root ).toString("hex")	).to.equal( dump.tree[0] );
+
+	const result			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_leaf_proof", {
+	    "tree_id": tree_addr,
+	    "label": "date_of_birth",
+	});
+
+	expect( result.hash_algorithm	).to.equal("Keccak256");
+
+	const verify			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_leaf_proof", {
+	    "proof": result.proof,
+	    "index": result.index,
+	    "leaf": result.leaf,
+	    "root": result.root,
+	    "total_leaves": result.total_leaves,
+	    "hash_algorithm": result.hash_algorithm,
+	});
+
+	expect( verify			).to.be.true;
+
+	const bundle			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "export_tree", tree_addr );
+	const imported_addr		= new ActionHash( await clients.bobby.call( DNA_NAME, MAIN_ZOME, "import_tree", bundle ) );
+	const imported_tree		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "get_tree", imported_addr );
+
+	expect( imported_tree.root	).to.deep.equal( keccak_tree.root );
+
+	await expect_reject( async () => {
+	    await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+		"leaves": flatten_data( drivers_license ),
+		"hash_algorithm": "Keccak256",
+		"padding": "PowerOfTwo",
+	    });
+	}, "Padding is only supported" );
+    });
+
+    it("should match the shared test vectors", async function () {
+	const vectors			= JSON.parse( fs.readFileSync( path.join( __dirname, "../vectors/merklicious_v1.json" ), "utf8" ) );
+
+	for ( let vector of vectors.cases ) {
+	    const tree_addr		= new ActionHash( await clients.alice.call( DNA_NAME, MAIN_ZOME, "create_tree", {
+		"leaves": vector.leaves.map( leaf => ({
+		    "label": leaf.label,
+		    "value": leaf.value,
+		}) ),
+		"entropy": Buffer.from( vector.entropy, "hex" ),
+		"padding": vector.padding,
+	    }) );
+	    const tree			= intoStruct( await clients.alice.call( DNA_NAME, MAIN_ZOME, "get_tree", tree_addr ), TreeStruct );
+
+	    expect( Buffer.from( tree.root ).toString("hex")	).to.equal( vector.root );
+	    expect( tree.leaves		).to.have.length( vector.leaves.length + ( vector.padding_leaves || [] ).length );
+
+	    for ( let [index, leaf] of vector.leaves.entries() ) {
+		const block_hash	= await clients.alice.call( DNA_NAME, MAIN_ZOME, "hash_data_block", {
+		    "label": leaf.label,
+		    "value": leaf.value,
+		    "salt": Buffer.from( leaf.salt, "hex" ),
+		});
+
+		expect( Buffer.from( block_hash ).toString("hex")	).to.equal( leaf.leaf );
+
+		const verify		= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "verify_leaf_proof", {
+		    "proof": vector.proofs[ index ].map( hash => Buffer.from( hash, "hex" ) ),
+		    "index": index,
+		    "leaf": Buffer.from( leaf.leaf, "hex" ),
+		    "root": Buffer.from( vector.root, "hex" ),
+		    "total_leaves": tree.leaves.length,
+		});
+
+		expect( verify		).to.be.true;
+	    }
+	}
+    });
+
     it("should generating output for docs", async function () {
 	const client			= {
 	    call ( ...args ) {
@@ -233,6 +1028,10 @@ describe("Minimal DNA", function () {
 	    let whoami			= await clients.alice.call( DNA_NAME, MAIN_ZOME, "whoami", null, 300_000 );
 	    log.normal("Alice whoami: %s", String(new HoloHash( whoami.agent_initial_pubkey )) );
 	}
+	{
+	    let whoami			= await clients.bobby.call( DNA_NAME, MAIN_ZOME, "whoami", null, 300_000 );
+	    log.normal("Bobby whoami: %s", String(new HoloHash( whoami.agent_initial_pubkey )) );
+	}
     });
 
     describe("Merklicious", function () {
@@ -31,10 +31,13 @@ export const EntryCreationActionStruct = {
 };
 
 export const TreeStruct = {
-    "data_blocks":		ActionHash,
+    "data_blocks":		OptionType( ActionHash ),
     "leaves":			VecType( Uint8Array ),
     "entropy":			Uint8Array,
     "root":			Uint8Array,
+    "tree_type":		String,
+    "layers":			OptionType( ActionHash ),
+    "chunks":			VecType( Object ),
 
     "metadata":			Object,
 };
@@ -30,6 +30,38 @@ pub enum EntryTypes {
     Tree(TreeEntry),
     #[entry_def]
     Claim(ClaimEntry),
+    #[entry_def(visibility = "private")]
+    TreeLayers(TreeLayersEntry),
+    #[entry_def(visibility = "private")]
+    LeafChunk(LeafChunkEntry),
+    #[entry_def(visibility = "private")]
+    Log(LogEntry),
+    #[entry_def(visibility = "private")]
+    LogAppend(LogAppendEntry),
+    #[entry_def(visibility = "private")]
+    Mmr(MmrEntry),
+    #[entry_def(visibility = "private")]
+    MmrAppend(MmrAppendEntry),
+    #[entry_def]
+    Anchor(AnchorEntry),
+    #[entry_def(visibility = "private")]
+    AggregateTree(AggregateTreeEntry),
+    #[entry_def]
+    Attestation(AttestationEntry),
+    #[entry_def(visibility = "private")]
+    CredentialRequest(CredentialRequestEntry),
+    #[entry_def(visibility = "private")]
+    ProofRequest(ProofRequestEntry),
+    #[entry_def(visibility = "private")]
+    Disclosure(DisclosureEntry),
+    #[entry_def(visibility = "private")]
+    Delegation(DelegationEntry),
+    #[entry_def]
+    EncryptedTree(EncryptedTreeEntry),
+    #[entry_def(visibility = "private")]
+    SentCredentialRequest(SentCredentialRequestEntry),
+    #[entry_def(visibility = "private")]
+    SentProofRequest(SentProofRequestEntry),
 }
 
 scoped_type_connector!(
@@ -44,6 +76,70 @@ scoped_type_connector!(
     EntryTypesUnit::Claim,
     EntryTypes::Claim( ClaimEntry )
 );
+scoped_type_connector!(
+    EntryTypesUnit::TreeLayers,
+    EntryTypes::TreeLayers( TreeLayersEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::LeafChunk,
+    EntryTypes::LeafChunk( LeafChunkEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Log,
+    EntryTypes::Log( LogEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::LogAppend,
+    EntryTypes::LogAppend( LogAppendEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Mmr,
+    EntryTypes::Mmr( MmrEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::MmrAppend,
+    EntryTypes::MmrAppend( MmrAppendEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Anchor,
+    EntryTypes::Anchor( AnchorEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::AggregateTree,
+    EntryTypes::AggregateTree( AggregateTreeEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Attestation,
+    EntryTypes::Attestation( AttestationEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::CredentialRequest,
+    EntryTypes::CredentialRequest( CredentialRequestEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::ProofRequest,
+    EntryTypes::ProofRequest( ProofRequestEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Disclosure,
+    EntryTypes::Disclosure( DisclosureEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::Delegation,
+    EntryTypes::Delegation( DelegationEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::EncryptedTree,
+    EntryTypes::EncryptedTree( EncryptedTreeEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::SentCredentialRequest,
+    EntryTypes::SentCredentialRequest( SentCredentialRequestEntry )
+);
+scoped_type_connector!(
+    EntryTypesUnit::SentProofRequest,
+    EntryTypes::SentProofRequest( SentProofRequestEntry )
+);
 
 
 
@@ -51,6 +147,11 @@ scoped_type_connector!(
 #[hdk_link_types]
 pub enum LinkTypes {
     Tree,
+    LogAppend,
+    MmrAppend,
+    Anchor,
+    Attestation,
+    Backup,
 }
 
 impl TryFrom<String> for LinkTypes {
@@ -60,6 +161,11 @@ impl TryFrom<String> for LinkTypes {
         Ok(
             match name.as_str() {
                 "Tree" => LinkTypes::Tree,
+                "LogAppend" => LinkTypes::LogAppend,
+                "MmrAppend" => LinkTypes::MmrAppend,
+                "Anchor" => LinkTypes::Anchor,
+                "Attestation" => LinkTypes::Attestation,
+                "Backup" => LinkTypes::Backup,
                 _ => return Err(guest_error!(format!("Unknown LinkTypes variant: {}", name ))),
             }
         )
@@ -72,9 +178,7 @@ impl<'de> Deserialize<'de> for LinkTypes {
         D: Deserializer<'de>,
     {
         let s: String = Deserialize::deserialize(deserializer)?;
-        Ok(
-            LinkTypes::try_from( s.clone() )
-                .or(Err(serde::de::Error::custom(format!("Unknown LinkTypes variant: {}", s))))?
-        )
+        LinkTypes::try_from( s.clone() )
+            .or(Err(serde::de::Error::custom(format!("Unknown LinkTypes variant: {}", s))))
     }
 }
@@ -9,6 +9,7 @@ use crate::hdi_extensions::{
 };
 use crate::{
     EntryTypes,
+    AnchorEntry,
 };
 
 pub fn validation(
@@ -20,6 +21,34 @@ pub fn validation(
             debug!("Checking EntryTypes::Tree");
             valid!()
         },
+        EntryTypes::Anchor(anchor) => {
+            debug!("Checking EntryTypes::Anchor");
+            if anchor.roots.is_empty() {
+                invalid!(format!("An anchor must contain at least 1 root"))
+            }
+
+            if AnchorEntry::calculate_root( &anchor.roots ) != Some( anchor.root ) {
+                invalid!(format!("Anchor root does not match the Merkle root of its roots"))
+            }
+
+            valid!()
+        },
+        EntryTypes::Attestation(attestation) => {
+            debug!("Checking EntryTypes::Attestation");
+            if attestation.issuer != create.author {
+                invalid!(format!("Attestation issuer ({}) must be the action author ({})", attestation.issuer, create.author ))
+            }
+
+            if !attestation.verify()? {
+                invalid!(format!("Attestation signature is not valid for issuer {}", attestation.issuer ))
+            }
+
+            valid!()
+        },
+        EntryTypes::EncryptedTree(_backup) => {
+            debug!("Checking EntryTypes::EncryptedTree");
+            valid!()
+        },
         _ => invalid!(format!("Create validation not implemented for entry type: {:#?}", create.entry_type )),
     }
 }
@@ -5,13 +5,20 @@ use crate::hdk::prelude::{
 use crate::hdi::prelude::*;
 use crate::hdi_extensions::{
     verify_app_entry_struct,
+    summon_app_entry,
+    summon_create_action,
+    detect_app_entry_unit,
     // Macros
     valid, invalid,
 };
 use crate::{
     // EntryTypes,
+    EntryTypesUnit,
     LinkTypes,
     TreeEntry,
+    AnchorEntry,
+    AttestationEntry,
+    EncryptedTreeEntry,
 };
 
 
@@ -38,7 +45,141 @@ pub fn validation(
             // Tree target should be a TreeEntry
             verify_app_entry_struct::<TreeEntry>( &target_address )?;
 
+            valid!()
+        },
+        LinkTypes::LogAppend => {
+            debug!("Checking LinkTypes::LogAppend");
+            validate_append_link(
+                "LogAppend", base_address, EntryTypesUnit::Log,
+                target_address, EntryTypesUnit::LogAppend, create,
+            )
+        },
+        LinkTypes::MmrAppend => {
+            debug!("Checking LinkTypes::MmrAppend");
+            validate_append_link(
+                "MmrAppend", base_address, EntryTypesUnit::Mmr,
+                target_address, EntryTypesUnit::MmrAppend, create,
+            )
+        },
+        LinkTypes::Anchor => {
+            debug!("Checking LinkTypes::Anchor");
+            // Anchor base should be an external hash of an anchored root
+            let root_hash = match base_address.clone().into_external_hash() {
+                Some(hash) => hash,
+                None => invalid!(format!("Anchor link base address must be an external hash; not '{}'", base_address )),
+            };
+
+            // Anchor target should be an AnchorEntry made by the link author
+            let anchor_id = match target_address.clone().into_action_hash() {
+                Some(hash) => hash,
+                None => invalid!(format!("Anchor link target address must be an action hash; not '{}'", target_address )),
+            };
+            let anchor_create = summon_create_action( &anchor_id )?;
+
+            if anchor_create.author != create.author {
+                invalid!(format!("Anchor links can only be created by the anchor's author ({})", anchor_create.author ))
+            }
+
+            let anchor : AnchorEntry = summon_app_entry( &target_address )?;
+
+            if !anchor.roots.iter().any(|root| root.as_slice() == root_hash.get_raw_32() ) {
+                invalid!(format!("Anchor {} does not include the root {}", anchor_id, root_hash ))
+            }
+
+            valid!()
+        },
+        LinkTypes::Attestation => {
+            debug!("Checking LinkTypes::Attestation");
+            // Attestation base should be an external hash of the attested root
+            let root_hash = match base_address.clone().into_external_hash() {
+                Some(hash) => hash,
+                None => invalid!(format!("Attestation link base address must be an external hash; not '{}'", base_address )),
+            };
+
+            // Attestation target should be an AttestationEntry made by the link author
+            let attestation : AttestationEntry = summon_app_entry( &target_address )?;
+
+            if attestation.issuer != create.author {
+                invalid!(format!("Attestation links can only be created by the issuer ({})", attestation.issuer ))
+            }
+
+            if attestation.root.as_slice() != root_hash.get_raw_32() {
+                invalid!(format!("Attestation link base does not match the attested root {}", root_hash ))
+            }
+
+            valid!()
+        },
+        LinkTypes::Backup => {
+            debug!("Checking LinkTypes::Backup");
+            // Backup base should be the author's agent pubkey
+            let agent_pubkey = match base_address.clone().into_agent_pub_key() {
+                Some(hash) => hash,
+                None => invalid!(format!("Backup link base address must be an agent pubkey; not '{}'", base_address )),
+            };
+
+            if agent_pubkey != create.author {
+                invalid!(format!("Creating a link based on an agent pubkey can only be made by the matching agent ({})", agent_pubkey ))
+            }
+
+            // Backup target should be an EncryptedTreeEntry made by the link author
+            let target_id = match target_address.clone().into_action_hash() {
+                Some(hash) => hash,
+                None => invalid!(format!("Backup link target must be an action hash; not '{}'", target_address )),
+            };
+
+            if summon_create_action( &target_id )?.author != create.author {
+                invalid!(format!("Backup links can only target the link author's own backups"))
+            }
+
+            verify_app_entry_struct::<EncryptedTreeEntry>( &target_address )?;
+
             valid!()
         },
     }
 }
+
+
+/// Check that an append link connects a collection entry to one of its appends, both made by the
+/// link author
+fn validate_append_link(
+    name: &str,
+    base_address: AnyLinkableHash,
+    base_unit: EntryTypesUnit,
+    target_address: AnyLinkableHash,
+    target_unit: EntryTypesUnit,
+    create: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    // Base should be a create action made by the link author
+    let base_id = match base_address.clone().into_action_hash() {
+        Some(hash) => hash,
+        None => invalid!(format!("{} link base address must be an action hash; not '{}'", name, base_address )),
+    };
+    let base_create = summon_create_action( &base_id )?;
+
+    if base_create.author != create.author {
+        invalid!(format!("{} links can only be created by the base entry's author ({})", name, base_create.author ))
+    }
+
+    let entry_type_unit : EntryTypesUnit = detect_app_entry_unit( &base_create )?;
+    if entry_type_unit != base_unit {
+        invalid!(format!("{} link base must be a {:?} entry; not {:?}", name, base_unit, entry_type_unit ))
+    }
+
+    // Target should be a create action made by the link author
+    let target_id = match target_address.clone().into_action_hash() {
+        Some(hash) => hash,
+        None => invalid!(format!("{} link target address must be an action hash; not '{}'", name, target_address )),
+    };
+    let target_create = summon_create_action( &target_id )?;
+
+    if target_create.author != create.author {
+        invalid!(format!("{} link target must be authored by the base entry's author ({})", name, create.author ))
+    }
+
+    let entry_type_unit : EntryTypesUnit = detect_app_entry_unit( &target_create )?;
+    if entry_type_unit != target_unit {
+        invalid!(format!("{} link target must be a {:?} entry; not {:?}", name, target_unit, entry_type_unit ))
+    }
+
+    valid!()
+}
@@ -7,7 +7,7 @@ use crate::hdi_extensions::{
     summon_create_action,
     detect_app_entry_unit,
     // Macros
-    invalid,
+    valid, invalid,
 };
 use crate::{
     EntryTypesUnit,
@@ -17,15 +17,68 @@ use crate::{
 pub fn validation(
     original_action_hash: ActionHash,
     _original_entry_hash: EntryHash,
-    _delete: Delete
+    delete: Delete
 ) -> ExternResult<ValidateCallbackResult> {
     let create = summon_create_action( &original_action_hash )?;
 
     match detect_app_entry_unit( &create )? {
-        EntryTypesUnit::Tree => {
-            debug!("Checking delete EntryTypesUnit::Tree");
+        EntryTypesUnit::Tree | EntryTypesUnit::AggregateTree => {
+            debug!("Checking delete EntryTypesUnit::{:?}", create.entry_type );
             invalid!("Trees cannot be deleted".to_string())
         },
+        EntryTypesUnit::Log | EntryTypesUnit::LogAppend => {
+            debug!("Checking delete EntryTypesUnit::{:?}", create.entry_type );
+            invalid!("Merkle logs are append-only".to_string())
+        },
+        EntryTypesUnit::Mmr | EntryTypesUnit::MmrAppend => {
+            debug!("Checking delete EntryTypesUnit::{:?}", create.entry_type );
+            invalid!("Merkle Mountain Ranges are append-only".to_string())
+        },
+        EntryTypesUnit::Anchor => {
+            debug!("Checking delete EntryTypesUnit::Anchor");
+            invalid!("Anchors cannot be deleted".to_string())
+        },
+        EntryTypesUnit::Attestation => {
+            debug!("Checking delete EntryTypesUnit::Attestation");
+            invalid!("Attestations cannot be deleted; unlink them from the root instead".to_string())
+        },
+        EntryTypesUnit::CredentialRequest | EntryTypesUnit::ProofRequest => {
+            debug!("Checking delete EntryTypesUnit::{:?}", create.entry_type );
+            // Requests are removed by the agent who received them once they are answered
+            if create.author != delete.author {
+                invalid!(format!("A request can only be deleted by the agent who received it ({})", create.author ))
+            }
+
+            valid!()
+        },
+        EntryTypesUnit::SentCredentialRequest | EntryTypesUnit::SentProofRequest => {
+            debug!("Checking delete EntryTypesUnit::{:?}", create.entry_type );
+            if create.author != delete.author {
+                invalid!(format!("A sent request can only be deleted by the agent who sent it ({})", create.author ))
+            }
+
+            valid!()
+        },
+        EntryTypesUnit::Disclosure => {
+            debug!("Checking delete EntryTypesUnit::Disclosure");
+            invalid!("Disclosures cannot be deleted".to_string())
+        },
+        EntryTypesUnit::Delegation => {
+            debug!("Checking delete EntryTypesUnit::Delegation");
+            if create.author != delete.author {
+                invalid!(format!("A delegation can only be revoked by the agent who granted it ({})", create.author ))
+            }
+
+            valid!()
+        },
+        EntryTypesUnit::EncryptedTree => {
+            debug!("Checking delete EntryTypesUnit::EncryptedTree");
+            if create.author != delete.author {
+                invalid!(format!("A tree backup can only be deleted by the agent who created it ({})", create.author ))
+            }
+
+            valid!()
+        },
         entry_type_unit => invalid!(format!("Delete validation not implemented for entry type: {:?}", entry_type_unit )),
     }
 }
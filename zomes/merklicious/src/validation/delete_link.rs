@@ -35,6 +35,35 @@ pub fn validation(
                 invalid!(format!("A group link can only be deleted by the author who created it ({})", create_link.author ))
             }
 
+            valid!()
+        },
+        LinkTypes::LogAppend => {
+            debug!("Checking LinkTypes::LogAppend delete");
+            invalid!(format!("Merkle logs are append-only; appends cannot be unlinked"))
+        },
+        LinkTypes::MmrAppend => {
+            debug!("Checking LinkTypes::MmrAppend delete");
+            invalid!(format!("Merkle Mountain Ranges are append-only; appends cannot be unlinked"))
+        },
+        LinkTypes::Anchor => {
+            debug!("Checking LinkTypes::Anchor delete");
+            invalid!(format!("Anchored roots cannot be unlinked"))
+        },
+        LinkTypes::Attestation => {
+            debug!("Checking LinkTypes::Attestation delete");
+            // Issuers can withdraw their attestation link
+            if create_link.author != delete.author {
+                invalid!(format!("An attestation link can only be deleted by the issuer who created it ({})", create_link.author ))
+            }
+
+            valid!()
+        },
+        LinkTypes::Backup => {
+            debug!("Checking LinkTypes::Backup delete");
+            if create_link.author != delete.author {
+                invalid!(format!("A backup link can only be deleted by the author who created it ({})", create_link.author ))
+            }
+
             valid!()
         },
     }
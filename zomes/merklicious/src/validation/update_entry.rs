@@ -23,6 +23,42 @@ pub fn validation(
             debug!("Checking update EntryTypes::Tree({:#?})", tree );
             invalid!(format!("Merkle Trees cannot be updated; use Create instead"))
         },
+        EntryTypes::AggregateTree(_) => {
+            debug!("Checking update EntryTypes::AggregateTree");
+            invalid!(format!("Aggregate trees cannot be updated; use Create instead"))
+        },
+        EntryTypes::Log(_) | EntryTypes::LogAppend(_) => {
+            debug!("Checking update EntryTypes::{:?}", update.entry_type );
+            invalid!(format!("Merkle logs are append-only; use 'append_leaves' instead"))
+        },
+        EntryTypes::Mmr(_) | EntryTypes::MmrAppend(_) => {
+            debug!("Checking update EntryTypes::{:?}", update.entry_type );
+            invalid!(format!("Merkle Mountain Ranges are append-only; use 'append_mmr_leaf' instead"))
+        },
+        EntryTypes::Anchor(_) => {
+            debug!("Checking update EntryTypes::Anchor");
+            invalid!(format!("Anchors cannot be updated; create a new anchor instead"))
+        },
+        EntryTypes::Attestation(_) => {
+            debug!("Checking update EntryTypes::Attestation");
+            invalid!(format!("Attestations cannot be updated; create a new attestation instead"))
+        },
+        EntryTypes::CredentialRequest(_) | EntryTypes::ProofRequest(_) => {
+            debug!("Checking update EntryTypes::{:?}", update.entry_type );
+            invalid!(format!("Requests cannot be updated"))
+        },
+        EntryTypes::Disclosure(_) => {
+            debug!("Checking update EntryTypes::Disclosure");
+            invalid!(format!("Disclosures cannot be updated"))
+        },
+        EntryTypes::Delegation(_) => {
+            debug!("Checking update EntryTypes::Delegation");
+            invalid!(format!("Delegations cannot be updated; revoke it and grant a new one instead"))
+        },
+        EntryTypes::EncryptedTree(_) => {
+            debug!("Checking update EntryTypes::EncryptedTree");
+            invalid!(format!("Tree backups cannot be updated; create a new backup instead"))
+        },
         _ => invalid!(format!("Update validation not implemented for entry type: {:#?}", update.entry_type )),
     }
 }
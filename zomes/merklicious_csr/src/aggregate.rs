@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    merklicious_sdk::{
+        // Entry Structs
+        AggregateSource,
+        AggregateTreeEntry,
+        AggregateLeafProofPayload,
+        TreeType,
+        // Input Structs
+        CreateAggregateTreeInput,
+        GetAggregateLeafProofInput,
+        ChainLeafProofInput,
+        GetLeafProofInput,
+    },
+};
+use crate::{
+    get_tree,
+    get_leaf_proof,
+};
+
+
+#[hdk_extern]
+pub fn create_aggregate_tree(input: CreateAggregateTreeInput) -> ExternResult<ActionHash> {
+    debug!("Creating aggregate tree from {} sub-trees", input.trees.len() );
+    let (roots, trees) : (Vec<[u8; 32]>, Vec<Option<ActionHash>>) = input.trees.into_iter()
+        .map(|source| match source {
+            AggregateSource::TreeId(tree_id) => {
+                let tree_entry = get_tree( tree_id.clone() )?;
+
+                // Only standard tree proofs can be chained
+                if tree_entry.tree_type != TreeType::Standard {
+                    return Err(guest_error!(format!("Tree {} is a {:?} tree; aggregate trees only support standard trees", tree_id, tree_entry.tree_type )));
+                }
+
+                Ok( (tree_entry.root.0, Some( tree_id )) )
+            },
+            AggregateSource::Root(root) => Ok( (root, None) ),
+        })
+        .collect::<ExternResult<Vec<([u8; 32], Option<ActionHash>)>>>()?
+        .into_iter()
+        .unzip();
+
+    let entry = AggregateTreeEntry {
+        root: AggregateTreeEntry::calculate_root( &roots )
+            .ok_or(guest_error!(format!("An aggregate tree must have at least 1 sub-tree")))?,
+        roots,
+        trees,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )
+}
+
+
+#[hdk_extern]
+pub fn get_aggregate_tree(aggregate_id: ActionHash) -> ExternResult<AggregateTreeEntry> {
+    debug!("Get aggregate tree entry: {}", aggregate_id );
+    let record = must_get( &aggregate_id )?;
+
+    AggregateTreeEntry::try_from_record( &record )
+}
+
+
+#[hdk_extern]
+pub fn get_aggregate_leaf_proof(input: GetAggregateLeafProofInput) -> ExternResult<AggregateLeafProofPayload> {
+    debug!("Get proof for '{}' in sub-tree {} of aggregate: {}", input.label, input.tree_id, input.aggregate_id );
+    let leaf_proof = get_leaf_proof( GetLeafProofInput {
+        tree_id: input.tree_id,
+        label: input.label,
+        recipient: input.recipient,
+    })?;
+
+    get_aggregate_tree( input.aggregate_id )?.chain_proof( leaf_proof )
+}
+
+
+/// Chain a leaf proof from any agent's sub-tree through an aggregate tree
+#[hdk_extern]
+pub fn chain_leaf_proof(input: ChainLeafProofInput) -> ExternResult<AggregateLeafProofPayload> {
+    get_aggregate_tree( input.aggregate_id )?.chain_proof( input.leaf_proof )
+}
+
+
+#[hdk_extern]
+pub fn verify_aggregate_leaf_proof(input: AggregateLeafProofPayload) -> ExternResult<bool> {
+    input.verify()
+}
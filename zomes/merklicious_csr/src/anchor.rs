@@ -0,0 +1,202 @@
+use std::collections::{ BTreeMap, BTreeSet };
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    LinkTypes,
+    merklicious_sdk::{
+        // Entry Structs
+        AnchorEntry,
+        AnchorProofPayload,
+        // Input Structs
+        CreateAnchorInput,
+        RequestAnchorInput,
+        GetAnchorProofInput,
+    },
+};
+use crate::{
+    call_remote_agent_with_secret,
+    root_hash,
+};
+
+
+/// The tag of the cap grants that allow agents to call [`handle_anchor_request`]
+const ANCHOR_REQUESTS_TAG: &str = "anchor_requests";
+
+
+#[hdk_extern]
+pub fn create_anchor(input: CreateAnchorInput) -> ExternResult<ActionHash> {
+    debug!("Anchoring {} roots", input.roots.len() );
+    let mut roots : Vec<[u8; 32]> = Vec::new();
+
+    for root in input.roots {
+        if !roots.contains( &root ) {
+            roots.push( root );
+        }
+    }
+
+    let entry = AnchorEntry {
+        root: AnchorEntry::calculate_root( &roots )
+            .ok_or(guest_error!(format!("An anchor must contain at least 1 root")))?,
+        roots,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let action_hash = create_entry( entry.to_input() )?;
+
+    for root in entry.roots.iter() {
+        create_link(
+            root_hash( root ),
+            action_hash.clone(),
+            LinkTypes::Anchor,
+            (),
+        )?;
+    }
+
+    Ok( action_hash )
+}
+
+
+/// Allow `requesters` to ask this agent to anchor roots
+///
+/// Anchors are public entries, so only agents given the returned `cap_secret` can make this agent
+/// write them.
+#[hdk_extern]
+pub fn allow_anchor_requests(requesters: Vec<AgentPubKey>) -> ExternResult<CapSecret> {
+    debug!("Allowing anchor requests from {} agents", requesters.len() );
+    let cap_secret = generate_cap_secret()?;
+    let assignees : BTreeSet<AgentPubKey> = requesters.into_iter().collect();
+
+    if assignees.is_empty() {
+        return Err(guest_error!(format!("Anchor requests must be allowed for at least 1 agent")));
+    }
+
+    create_cap_grant( CapGrantEntry {
+        tag: String::from( ANCHOR_REQUESTS_TAG ),
+        access: CapAccess::Assigned {
+            secret: cap_secret,
+            assignees,
+        },
+        functions: GrantedFunctions::Listed( vec![
+            ( zome_info()?.name, FunctionName::from("handle_anchor_request") ),
+        ].into_iter().collect() ),
+    })?;
+
+    Ok( cap_secret )
+}
+
+
+/// Delete every cap grant made by [`allow_anchor_requests`]
+#[hdk_extern]
+pub fn revoke_anchor_requests(_: ()) -> ExternResult<Vec<ActionHash>> {
+    debug!("Revoking all anchor request grants");
+    query(
+        ChainQueryFilter::new()
+            .entry_type( EntryType::CapGrant )
+            .include_entries( true )
+    )?.into_iter()
+        .filter(|record| match record.entry().as_option() {
+            Some(Entry::CapGrant(grant)) => grant.tag == ANCHOR_REQUESTS_TAG,
+            _ => false,
+        })
+        .map(|record| delete_cap_grant( record.action_address().to_owned() ) )
+        .collect()
+}
+
+
+/// Anchor roots on behalf of a remote agent (see [`request_anchor`])
+///
+/// Only callable through a cap grant from [`allow_anchor_requests`].
+#[hdk_extern]
+pub fn handle_anchor_request(roots: Vec<[u8; 32]>) -> ExternResult<ActionHash> {
+    let provenance = call_info()?.provenance;
+    debug!("Received anchor request from {} for {} roots", provenance, roots.len() );
+
+    create_anchor( CreateAnchorInput { roots } )
+}
+
+
+/// Ask `witness` to anchor the given roots on their source chain
+#[hdk_extern]
+pub fn request_anchor(input: RequestAnchorInput) -> ExternResult<ActionHash> {
+    debug!("Requesting anchor from witness: {}", input.witness );
+    call_remote_agent_with_secret( &input.witness, "handle_anchor_request", Some( input.cap_secret ), input.roots )
+}
+
+
+#[hdk_extern]
+pub fn get_anchor(anchor_id: ActionHash) -> ExternResult<AnchorEntry> {
+    debug!("Get anchor entry: {}", anchor_id );
+    let record = must_get( &anchor_id )?;
+
+    AnchorEntry::try_from_record( &record )
+}
+
+
+#[hdk_extern]
+pub fn get_anchors_for_root(root: [u8; 32]) -> ExternResult<Vec<ActionHash>> {
+    Ok(
+        get_links( root_hash( &root ), LinkTypes::Anchor, None )?
+            .into_iter()
+            .filter_map(|link| link.target.into_action_hash() )
+            .collect()
+    )
+}
+
+
+#[hdk_extern]
+pub fn get_anchor_proof(input: GetAnchorProofInput) -> ExternResult<AnchorProofPayload> {
+    let record = match input.anchor_id {
+        Some(ref anchor_id) => must_get( anchor_id )?,
+        None => {
+            // The earliest anchor is the strongest claim about when the root existed
+            get_anchors_for_root( input.root )?
+                .iter()
+                .map( must_get )
+                .collect::<ExternResult<Vec<Record>>>()?
+                .into_iter()
+                .min_by_key(|record| record.action().timestamp() )
+                .ok_or(guest_error!(format!("Root {} has not been anchored", root_hash( &input.root ) )))?
+        },
+    };
+    let anchor = AnchorEntry::try_from_record( &record )?;
+    let index = anchor.roots.iter()
+        .position(|root| *root == input.root )
+        .ok_or(guest_error!(format!("Anchor {} does not include root {}", record.action_address(), root_hash( &input.root ) )))?;
+    let proof = anchor.proof( index )
+        .ok_or(guest_error!(format!("Couldn't create proof for root {} of anchor {}", index, record.action_address() )))?;
+
+    Ok(
+        AnchorProofPayload {
+            anchor_id: record.action_address().to_owned(),
+            witness: record.action().author().to_owned(),
+            anchored_at: record.action().timestamp(),
+            root: input.root,
+            index: index as u64,
+            total_roots: anchor.roots.len() as u64,
+            proof,
+            anchor_root: anchor.root,
+        }
+    )
+}
+
+
+/// Verify an anchor proof against the anchor record on the DHT
+#[hdk_extern]
+pub fn verify_anchor_proof(input: AnchorProofPayload) -> ExternResult<bool> {
+    let record = must_get( &input.anchor_id )?;
+    let anchor = AnchorEntry::try_from_record( &record )?;
+
+    Ok(
+        input.verify()
+            && anchor.root == input.anchor_root
+            && *record.action().author() == input.witness
+            && record.action().timestamp() == input.anchored_at
+    )
+}
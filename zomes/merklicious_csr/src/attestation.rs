@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    LinkTypes,
+    merklicious_sdk::{
+        // Entry Structs
+        AttestationEntry,
+        // Input Structs
+        AttestRootInput,
+        VerifyAttestedLeafProofInput,
+    },
+};
+use crate::{
+    root_hash,
+};
+
+
+/// Create and link an attestation of a holder's root as the current agent
+pub fn create_attestation(input: AttestRootInput) -> ExternResult<(ActionHash, AttestationEntry)> {
+    debug!("Attesting root for holder: {}", input.holder );
+    let issuer = agent_info()?.agent_initial_pubkey;
+    let signature = sign_raw(
+        issuer.clone(),
+        AttestationEntry::signed_content( &input.holder, &input.root ),
+    )?;
+    let entry = AttestationEntry {
+        issuer,
+        holder: input.holder,
+        root: input.root,
+        signature,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let action_hash = create_entry( entry.to_input() )?;
+
+    create_link(
+        root_hash( &entry.root ),
+        action_hash.clone(),
+        LinkTypes::Attestation,
+        (),
+    )?;
+
+    Ok( (action_hash, entry) )
+}
+
+
+#[hdk_extern]
+pub fn attest_root(input: AttestRootInput) -> ExternResult<ActionHash> {
+    Ok( create_attestation( input )?.0 )
+}
+
+
+#[hdk_extern]
+pub fn get_attestation(attestation_id: ActionHash) -> ExternResult<AttestationEntry> {
+    debug!("Get attestation entry: {}", attestation_id );
+    let record = must_get( &attestation_id )?;
+
+    AttestationEntry::try_from_record( &record )
+}
+
+
+#[hdk_extern]
+pub fn get_attestations_for_root(root: [u8; 32]) -> ExternResult<Vec<AttestationEntry>> {
+    get_links( root_hash( &root ), LinkTypes::Attestation, None )?
+        .into_iter()
+        .map(|link| {
+            let attestation_id = link.target.into_action_hash()
+                .ok_or(guest_error!(format!("Attestation link target is not an action hash")))?;
+
+            get_attestation( attestation_id )
+        })
+        .collect()
+}
+
+
+/// Verify a leaf proof and that its root is attested by `issuer`
+#[hdk_extern]
+pub fn verify_attested_leaf_proof(input: VerifyAttestedLeafProofInput) -> ExternResult<bool> {
+    if !input.leaf_proof.verify()? {
+        return Ok( false );
+    }
+
+    for attestation in get_attestations_for_root( input.leaf_proof.root.0 )? {
+        if attestation.issuer == input.issuer && attestation.verify()? {
+            return Ok( true );
+        }
+    }
+
+    Ok( false )
+}
@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    LinkTypes,
+    merklicious_sdk::{
+        verify::VerifyError,
+        padding_leaf,
+        MerkleHash,
+        // Entry Structs
+        LeafDataBlock,
+        LeafInput,
+        LeafProofPayload,
+        TreeType,
+        EncryptedTreeEntry,
+        TreeBackupHeader,
+        // Input Structs
+        CreateTreeInput,
+        BackupTreeInput,
+        GetBackupLeafProofInput,
+    },
+};
+use crate::{
+    create_tree,
+    get_tree,
+    tree_data_blocks,
+};
+
+
+fn encrypt<T>(sender: &X25519PubKey, recipient: &X25519PubKey, data: &T) -> ExternResult<XSalsa20Poly1305EncryptedData>
+where
+    T: serde::Serialize + std::fmt::Debug,
+{
+    let bytes = rmp_serde::to_vec( data )
+        .map_err(|err| guest_error!(format!("Failed to serialize backup data: {:?}", err )))?;
+
+    x_25519_x_salsa20_poly1305_encrypt( *sender, *recipient, bytes.into() )
+}
+
+
+fn decrypt<T>(backup: &EncryptedTreeEntry, data: &XSalsa20Poly1305EncryptedData) -> ExternResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let bytes = x_25519_x_salsa20_poly1305_decrypt( backup.recipient, backup.sender, data.to_owned() )?
+        .ok_or(guest_error!(format!("Couldn't decrypt backup; the recovery key is not in this keystore")))?;
+
+    rmp_serde::from_slice( bytes.as_ref() )
+        .map_err(|err| guest_error!(format!("Failed to deserialize backup data: {:?}", err )))
+}
+
+
+/// Decrypt a backup's header and data blocks
+fn open_backup(backup_id: &ActionHash) -> ExternResult<(TreeBackupHeader, Vec<LeafDataBlock>)> {
+    let backup = get_tree_backup( backup_id.to_owned() )?;
+    let header : TreeBackupHeader = decrypt( &backup, &backup.header )?;
+    let data_blocks : Vec<LeafDataBlock> = decrypt( &backup, &backup.data_blocks )?;
+
+    Ok( (header, data_blocks) )
+}
+
+
+/// Create an x25519 key in this agent's keystore for other devices to use as a `recovery_key`
+///
+/// Call this on the recovery device; [`backup_tree`] rejects keys from its own keystore.
+#[hdk_extern]
+pub fn create_recovery_key(_: ()) -> ExternResult<X25519PubKey> {
+    create_x25519_keypair()
+}
+
+
+/// Check if the secret half of `key` is in this agent's keystore
+///
+/// Encrypting needs the sender's secret, so it only succeeds for local keys.
+fn is_local_key(key: &X25519PubKey) -> bool {
+    x_25519_x_salsa20_poly1305_encrypt( *key, *key, Vec::new().into() ).is_ok()
+}
+
+
+/// Store an encrypted copy of a tree so it can be recovered if this device is lost
+///
+/// The backup is public, so only the data needed to recreate the tree is stored and all of it is
+/// encrypted for `recovery_key`, which must come from another device's keystore.
+#[hdk_extern]
+pub fn backup_tree(input: BackupTreeInput) -> ExternResult<ActionHash> {
+    debug!("Backing up tree: {}", input.tree_id );
+    if is_local_key( &input.recovery_key ) {
+        return Err(guest_error!(format!("Recovery key is in this device's keystore; use a key from the recovery device")));
+    }
+
+    let tree_entry = get_tree( input.tree_id.clone() )?;
+    let data_blocks = tree_data_blocks( &input.tree_id, &tree_entry )?;
+    let header = TreeBackupHeader {
+        root: tree_entry.root.0,
+        entropy: tree_entry.entropy.clone(),
+        tree_type: tree_entry.tree_type.clone(),
+        total_leaves: tree_entry.leaf_count(),
+        store_layers: tree_entry.layers.is_some(),
+        chunk_size: Some( tree_entry.effective_chunk_size() ),
+        hash_algorithm: tree_entry.hash_algorithm.clone(),
+    };
+
+    let sender = create_x25519_keypair()?;
+    let recipient = input.recovery_key;
+    let entry = EncryptedTreeEntry {
+        sender,
+        recipient,
+        header: encrypt( &sender, &recipient, &header )?,
+        data_blocks: encrypt( &sender, &recipient, &data_blocks )?,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let action_hash = create_entry( entry.to_input() )?;
+
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        action_hash.clone(),
+        LinkTypes::Backup,
+        (),
+    )?;
+
+    Ok( action_hash )
+}
+
+
+#[hdk_extern]
+pub fn get_tree_backup(backup_id: ActionHash) -> ExternResult<EncryptedTreeEntry> {
+    debug!("Get tree backup entry: {}", backup_id );
+    let record = must_get( &backup_id )?;
+
+    EncryptedTreeEntry::try_from_record( &record )
+}
+
+
+#[hdk_extern]
+pub fn get_tree_backups(agent: AgentPubKey) -> ExternResult<Vec<ActionHash>> {
+    Ok(
+        get_links( agent, LinkTypes::Backup, None )?
+            .into_iter()
+            .filter_map(|link| link.target.into_action_hash() )
+            .collect()
+    )
+}
+
+
+/// Get a leaf proof straight from an encrypted backup of a standard tree
+///
+/// The data blocks are only decrypted in memory for generating the proof.
+#[hdk_extern]
+pub fn get_backup_leaf_proof(input: GetBackupLeafProofInput) -> ExternResult<LeafProofPayload> {
+    debug!("Get proof for '{}' in backup: {}", input.label, input.backup_id );
+    let (header, data_blocks) = open_backup( &input.backup_id )?;
+
+    if header.tree_type != TreeType::Standard {
+        return Err(guest_error!(format!("Backup {} is of a {:?} tree; recover it to get proofs", input.backup_id, header.tree_type )));
+    }
+
+    let mut leaves = data_blocks.iter()
+        .map(|block| header.hash_algorithm.leaf_hash( block ) )
+        .collect::<Result<Vec<[u8; 32]>, VerifyError>>()?;
+
+    for index in leaves.len()..header.total_leaves as usize {
+        leaves.push( padding_leaf( &header.entropy, index )? );
+    }
+
+    if header.hash_algorithm.root( &leaves ) != Some( header.root ) {
+        return Err(guest_error!(format!("Backup {} data blocks do not match its root", input.backup_id )));
+    }
+
+    let target_index = data_blocks.iter()
+        .position(|block| block.label == input.label )
+        .ok_or(guest_error!(format!("Backup has no data block with the label '{}'", input.label )))?;
+    let proof = header.hash_algorithm.proof( &leaves, target_index )
+        .ok_or(guest_error!(format!("Couldn't create proof for leaf {} of backup {}", target_index, input.backup_id )))?;
+
+    Ok(
+        LeafProofPayload {
+            proof: MerkleHash::from_raw_vec( proof ),
+            index: target_index as u64,
+            target: data_blocks[ target_index ].clone(),
+            leaf: leaves[ target_index ].into(),
+            root: header.root.into(),
+            total_leaves: leaves.len() as u64,
+            hash_algorithm: header.hash_algorithm,
+        }
+    )
+}
+
+
+/// Rebuild the private tree entries on this source chain from an encrypted backup
+///
+/// Returns the ID of the recreated tree, which has the same root as the original.
+#[hdk_extern]
+pub fn recover_tree(backup_id: ActionHash) -> ExternResult<ActionHash> {
+    debug!("Recovering tree from backup: {}", backup_id );
+    let (header, data_blocks) = open_backup( &backup_id )?;
+    let padding = header.padding( data_blocks.len() );
+    let leaves = data_blocks.into_iter()
+        .map(|block| LeafInput {
+            label: block.label,
+            value: block.value,
+        })
+        .collect();
+
+    let tree_id = create_tree( CreateTreeInput {
+        leaves,
+        entropy: Some( serde_bytes::ByteBuf::from( header.entropy ) ),
+        tree_type: Some( header.tree_type ),
+        padding,
+        store_layers: Some( header.store_layers ),
+        chunk_size: header.chunk_size,
+        hash_algorithm: Some( header.hash_algorithm ),
+        metadata: None,
+    })?;
+
+    if get_tree( tree_id.clone() )?.root != header.root {
+        return Err(guest_error!(format!("Recovered tree does not match the root of backup {}", backup_id )));
+    }
+
+    Ok( tree_id )
+}
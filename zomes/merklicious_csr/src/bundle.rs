@@ -0,0 +1,77 @@
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    guest_error,
+};
+use merklicious::{
+    merklicious_sdk::{
+        TREE_BUNDLE_VERSION,
+        TreeBundle,
+        LeafInput,
+        // Input Structs
+        CreateTreeInput,
+    },
+};
+use crate::{
+    create_tree,
+    get_tree,
+    tree_data_blocks,
+    tree_leaves,
+};
+
+
+/// Export everything needed to recreate a tree in another cell
+#[hdk_extern]
+pub fn export_tree(tree_id: ActionHash) -> ExternResult<TreeBundle> {
+    debug!("Exporting tree: {}", tree_id );
+    let tree_entry = get_tree( tree_id.clone() )?;
+
+    Ok(
+        TreeBundle {
+            version: TREE_BUNDLE_VERSION,
+            hash_algorithm: tree_entry.hash_algorithm.clone(),
+            tree_type: tree_entry.tree_type.clone(),
+            entropy: tree_entry.entropy.clone(),
+            data_blocks: tree_data_blocks( &tree_id, &tree_entry )?,
+            leaves: tree_leaves( &tree_entry )?,
+            root: tree_entry.root.0,
+            store_layers: tree_entry.layers.is_some(),
+            chunk_size: Some( tree_entry.effective_chunk_size() ),
+            metadata: tree_entry.metadata,
+        }
+    )
+}
+
+
+/// Recreate a tree from an exported bundle
+///
+/// The bundle is rejected if its salts, leaves or root cannot be recomputed from its data blocks.
+#[hdk_extern]
+pub fn import_tree(bundle: TreeBundle) -> ExternResult<ActionHash> {
+    debug!("Importing tree with {} data blocks", bundle.data_blocks.len() );
+    bundle.verify()?;
+
+    let padding = bundle.padding();
+    let leaves = bundle.data_blocks.into_iter()
+        .map(|block| LeafInput {
+            label: block.label,
+            value: block.value,
+        })
+        .collect();
+
+    let tree_id = create_tree( CreateTreeInput {
+        leaves,
+        entropy: Some( serde_bytes::ByteBuf::from( bundle.entropy ) ),
+        tree_type: Some( bundle.tree_type ),
+        padding,
+        store_layers: Some( bundle.store_layers ),
+        chunk_size: bundle.chunk_size,
+        hash_algorithm: Some( bundle.hash_algorithm ),
+        metadata: Some( bundle.metadata ),
+    })?;
+
+    if get_tree( tree_id.clone() )?.root != bundle.root {
+        return Err(guest_error!(format!("Imported tree does not match the bundle root")));
+    }
+
+    Ok( tree_id )
+}
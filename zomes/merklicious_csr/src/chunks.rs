@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    merklicious_sdk::{
+        merkle_layers,
+        HashAlgorithm,
+        MerkleHash,
+        // Entry Structs
+        LeafDataBlock,
+        LeafProofPayload,
+        DataBlocksEntry,
+        LeafChunkEntry,
+        TreeChunk,
+        TreeEntry,
+        // Input Structs
+        GetLeafProofInput,
+    },
+};
+use crate::{
+    get_data_blocks,
+};
+
+
+/// Store the data blocks and leaves of a large tree as one entry pair per chunk
+///
+/// Leaves past the end of `data_blocks` (ie. padding) are stored in chunks without data blocks.
+pub fn create_tree_chunks(data_blocks: Vec<LeafDataBlock>, leaves: &[[u8; 32]], chunk_size: usize) -> ExternResult<Vec<TreeChunk>> {
+    let mut blocks = data_blocks.into_iter();
+
+    leaves.chunks( chunk_size )
+        .map(|chunk_leaves| -> ExternResult<TreeChunk> {
+            let chunk_blocks : Vec<LeafDataBlock> = blocks.by_ref()
+                .take( chunk_leaves.len() )
+                .collect();
+            let labels = chunk_blocks.iter()
+                .map(|block| block.label.clone() )
+                .collect();
+            let data_blocks = match chunk_blocks.is_empty() {
+                true => None,
+                false => {
+                    let blocks_entry = DataBlocksEntry {
+                        blocks: chunk_blocks,
+
+                        // common fields
+                        metadata: BTreeMap::new(),
+                    };
+                    Some( create_entry( blocks_entry.to_input() )? )
+                },
+            };
+            let root = merkle_layers::root_from_layers( chunk_leaves, &merkle_layers::build_layers( chunk_leaves ) )
+                .ok_or(guest_error!(format!("Couldn't get the Merkle root of chunk")))?;
+            let leaves_entry = LeafChunkEntry {
+                leaves: chunk_leaves.to_vec(),
+
+                // common fields
+                metadata: BTreeMap::new(),
+            };
+
+            Ok(
+                TreeChunk {
+                    data_blocks,
+                    leaves: create_entry( leaves_entry.to_input() )?,
+                    labels,
+                    size: chunk_leaves.len() as u64,
+                    root,
+                }
+            )
+        })
+        .collect()
+}
+
+
+#[hdk_extern]
+pub fn get_leaf_chunk(leaf_chunk_id: ActionHash) -> ExternResult<LeafChunkEntry> {
+    debug!("Get leaf chunk entry: {}", leaf_chunk_id );
+    let record = must_get( &leaf_chunk_id )?;
+
+    LeafChunkEntry::try_from_record( &record )
+}
+
+
+/// Build a leaf proof for a chunked tree while only loading the chunk that contains the label
+pub fn get_chunked_leaf_proof(input: &GetLeafProofInput, tree_entry: &TreeEntry) -> ExternResult<LeafProofPayload> {
+    let (chunk_index, position) = tree_entry.chunks.iter()
+        .enumerate()
+        .find_map(|(chunk_index, chunk)| {
+            chunk.labels.iter()
+                .position(|label| *label == input.label )
+                .map(|position| (chunk_index, position) )
+        })
+        .ok_or(guest_error!(format!("Tree has no data block with the label '{}'", input.label )))?;
+    let chunk = &tree_entry.chunks[ chunk_index ];
+    let data_blocks_id = chunk.data_blocks.clone()
+        .ok_or(guest_error!(format!("Chunk {} of tree {} has no data blocks", chunk_index, input.tree_id )))?;
+    let target = get_data_blocks( data_blocks_id )?.blocks
+        .get( position ).cloned()
+        .ok_or(guest_error!(format!("Chunk {} of tree {} is missing data block '{}'", chunk_index, input.tree_id, input.label )))?;
+    let leaf = target.hash()?;
+    let chunk_leaves = get_leaf_chunk( chunk.leaves.clone() )?.leaves;
+
+    if chunk_leaves.get( position ) != Some( &leaf ) {
+        return Err(guest_error!(format!("Data block '{}' does not match leaf {} of chunk {} in tree {}", input.label, position, chunk_index, input.tree_id )));
+    }
+
+    let chunk_roots : Vec<[u8; 32]> = tree_entry.chunks.iter()
+        .map(|chunk| chunk.root )
+        .collect();
+    let proof = merkle_layers::chunked_proof( &chunk_leaves, position, &chunk_roots, chunk_index )
+        .ok_or(guest_error!(format!("Couldn't build proof for leaf {} of chunk {}", position, chunk_index )))?;
+    let offset : u64 = tree_entry.chunks[ ..chunk_index ].iter()
+        .map(|chunk| chunk.size )
+        .sum();
+
+    Ok(
+        LeafProofPayload {
+            proof: MerkleHash::from_raw_vec( proof ),
+            index: offset + position as u64,
+            target,
+            leaf: leaf.into(),
+            root: tree_entry.root,
+            total_leaves: tree_entry.leaf_count(),
+            hash_algorithm: HashAlgorithm::Sha256,
+        }
+    )
+}
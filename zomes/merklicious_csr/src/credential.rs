@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use rand::Rng;
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    EntryTypesUnit,
+    merklicious_sdk::{
+        verify::VerifyError,
+        // Entry Structs
+        LeafDataBlock,
+        LeafInput,
+        CredentialRequestEntry,
+        SentCredentialRequestEntry,
+        PendingCredentialRequest,
+        IssuedCredentialPayload,
+        CredentialIssuancePayload,
+        MerkliciousSignal,
+        // Input Structs
+        CreateTreeInput,
+        AttestRootInput,
+        RequestCredentialInput,
+    },
+};
+use crate::{
+    call_remote_agent,
+    query_pending_records,
+    create_tree,
+    create_merkle_tree,
+    hash_data_blocks,
+    attestation::{
+        create_attestation,
+    },
+};
+
+
+fn credential_root(data_blocks: &[LeafDataBlock]) -> ExternResult<[u8; 32]> {
+    create_merkle_tree( &hash_data_blocks( data_blocks )? ).root()
+        .ok_or(guest_error!(format!("Couldn't get the Merkle root")))
+}
+
+
+//
+// Holder
+//
+/// Ask `issuer` to issue a credential for the given leaves
+///
+/// The request is also recorded on this agent's source chain so that [`receive_credential`] only
+/// accepts credentials that were asked for.  Returns the ID of the request on the issuer's source
+/// chain.
+#[hdk_extern]
+pub fn request_credential(input: RequestCredentialInput) -> ExternResult<ActionHash> {
+    debug!("Requesting credential from issuer: {}", input.issuer );
+    let request_id : ActionHash = call_remote_agent( &input.issuer, "handle_credential_request", input.leaves.clone() )?;
+    let entry = SentCredentialRequestEntry {
+        issuer: input.issuer,
+        request_id: request_id.clone(),
+        leaves: input.leaves,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )?;
+
+    Ok( request_id )
+}
+
+
+/// Find the sent request that an issuer's credential answers
+fn get_sent_credential_request(
+    issuer: &AgentPubKey,
+    request_id: &ActionHash,
+) -> ExternResult<(ActionHash, SentCredentialRequestEntry)> {
+    for record in query_pending_records( EntryTypesUnit::SentCredentialRequest )? {
+        let request = SentCredentialRequestEntry::try_from_record( &record )?;
+
+        if request.issuer == *issuer && request.request_id == *request_id {
+            return Ok( (record.action_address().to_owned(), request) );
+        }
+    }
+
+    Err(guest_error!(format!("No pending credential request {} to issuer {}", request_id, issuer )))
+}
+
+
+/// Receive a credential from an issuer and store it as a tree
+#[hdk_extern]
+pub fn receive_credential(input: IssuedCredentialPayload) -> ExternResult<ActionHash> {
+    let issuer = call_info()?.provenance;
+    let holder = agent_info()?.agent_initial_pubkey;
+    debug!("Received credential from issuer: {}", issuer );
+    let (sent_request_id, sent_request) = get_sent_credential_request( &issuer, &input.request_id )?;
+
+    if input.attestation.issuer != issuer || input.attestation.holder != holder {
+        return Err(guest_error!(format!("Credential attestation is not from {} for {}", issuer, holder )));
+    }
+
+    if input.attestation.root != input.root || !input.attestation.verify()? {
+        return Err(guest_error!(format!("Credential attestation is not valid for root {:?}", input.root )));
+    }
+
+    // The tree is recreated from the entropy, so the salts must match the entropy as well
+    let leaves : Vec<LeafInput> = input.data_blocks.iter()
+        .map(|block| LeafInput {
+            label: block.label.clone(),
+            value: block.value.clone(),
+        })
+        .collect();
+
+    if leaves.len() != sent_request.leaves.len()
+        || leaves.iter().zip( sent_request.leaves.iter() )
+            .any(|(leaf, requested)| leaf.label != requested.label || leaf.value != requested.value )
+    {
+        return Err(guest_error!(format!("Credential leaves do not match request {}", input.request_id )));
+    }
+
+    let data_blocks = leaves.clone().into_iter()
+        .enumerate()
+        .map(|(index, leaf)| leaf.into_data_block( &input.entropy, index ) )
+        .collect::<Result<Vec<LeafDataBlock>, VerifyError>>()?;
+
+    if credential_root( &data_blocks )? != input.root {
+        return Err(guest_error!(format!("Credential data blocks do not match root {:?}", input.root )));
+    }
+
+    let tree_id = create_tree( CreateTreeInput {
+        leaves,
+        entropy: Some( serde_bytes::ByteBuf::from( input.entropy ) ),
+        tree_type: None,
+        padding: None,
+        store_layers: None,
+        chunk_size: None,
+        hash_algorithm: None,
+        metadata: None,
+    })?;
+
+    delete_entry( sent_request_id )?;
+
+    emit_signal( MerkliciousSignal::CredentialReceived {
+        tree_id: tree_id.clone(),
+        issuer,
+    })?;
+
+    Ok( tree_id )
+}
+
+
+
+//
+// Issuer
+//
+/// Store a credential request from a remote holder until it is issued or rejected
+#[hdk_extern]
+pub fn handle_credential_request(leaves: Vec<LeafInput>) -> ExternResult<ActionHash> {
+    let holder = call_info()?.provenance;
+    debug!("Received credential request from holder: {}", holder );
+    let entry = CredentialRequestEntry {
+        holder: holder.clone(),
+        leaves,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let request_id = create_entry( entry.to_input() )?;
+
+    emit_signal( MerkliciousSignal::CredentialRequested {
+        request_id: request_id.clone(),
+        holder,
+    })?;
+
+    Ok( request_id )
+}
+
+
+#[hdk_extern]
+pub fn get_credential_requests(_: ()) -> ExternResult<Vec<PendingCredentialRequest>> {
+    query_pending_records( EntryTypesUnit::CredentialRequest )?
+        .into_iter()
+        .map(|record| Ok(
+            PendingCredentialRequest {
+                request_id: record.action_address().to_owned(),
+                request: CredentialRequestEntry::try_from_record( &record )?,
+            }
+        ))
+        .collect()
+}
+
+
+/// Get a credential request that has not been issued or rejected yet
+fn get_pending_credential_request(request_id: &ActionHash) -> ExternResult<CredentialRequestEntry> {
+    let record = query_pending_records( EntryTypesUnit::CredentialRequest )?
+        .into_iter()
+        .find(|record| record.action_address() == request_id )
+        .ok_or(guest_error!(format!("Credential request {} is not pending", request_id )))?;
+
+    CredentialRequestEntry::try_from_record( &record )
+}
+
+
+/// Create and attest the requested tree, then send it privately to the holder
+#[hdk_extern]
+pub fn issue_credential(request_id: ActionHash) -> ExternResult<CredentialIssuancePayload> {
+    debug!("Issuing credential for request: {}", request_id );
+    let request = get_pending_credential_request( &request_id )?;
+    let entropy : Vec<u8> = {
+        let mut rng = rand::thread_rng();
+        (0..32).map(|_| rng.gen()).collect()
+    };
+    let data_blocks = request.leaves.clone().into_iter()
+        .enumerate()
+        .map(|(index, leaf)| leaf.into_data_block( &entropy, index ) )
+        .collect::<Result<Vec<LeafDataBlock>, VerifyError>>()?;
+    let root = credential_root( &data_blocks )?;
+
+    let tree_id = create_tree( CreateTreeInput {
+        leaves: request.leaves,
+        entropy: Some( serde_bytes::ByteBuf::from( entropy.clone() ) ),
+        tree_type: None,
+        padding: None,
+        store_layers: None,
+        chunk_size: None,
+        hash_algorithm: None,
+        metadata: None,
+    })?;
+    let (_, attestation) = create_attestation( AttestRootInput {
+        holder: request.holder.clone(),
+        root,
+    })?;
+
+    let holder_tree_id = call_remote_agent(
+        &request.holder,
+        "receive_credential",
+        IssuedCredentialPayload {
+            request_id: request_id.clone(),
+            entropy,
+            data_blocks,
+            root,
+            attestation,
+        },
+    )?;
+
+    delete_entry( request_id )?;
+
+    Ok(
+        CredentialIssuancePayload {
+            tree_id,
+            holder_tree_id,
+            root,
+        }
+    )
+}
+
+
+/// Remove a credential request without issuing it
+#[hdk_extern]
+pub fn reject_credential_request(request_id: ActionHash) -> ExternResult<ActionHash> {
+    debug!("Rejecting credential request: {}", request_id );
+    get_pending_credential_request( &request_id )?;
+
+    delete_entry( request_id )
+}
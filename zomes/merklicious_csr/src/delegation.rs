@@ -0,0 +1,176 @@
+use std::collections::{ BTreeMap, BTreeSet };
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    EntryTypesUnit,
+    merklicious_sdk::{
+        // Entry Structs
+        DelegationEntry,
+        ProofDelegation,
+        ProofDelegationPayload,
+        LeafProofPayload,
+        SparseLeafProofPayload,
+        // Input Structs
+        GrantProofDelegationInput,
+        DelegatedProofInput,
+        GetLeafProofInput,
+    },
+};
+use crate::{
+    call_remote_agent_with_secret,
+    query_pending_records,
+};
+
+
+/// The proof externs that a delegation's cap grant allows
+const DELEGATED_FUNCTIONS: [&str; 2] = [
+    "get_leaf_proof",
+    "get_sparse_proof",
+];
+
+
+/// Check that the current call is allowed to get a proof for `label` in `tree_id`
+///
+/// Returns `None` when the chain author is calling, or the delegate when the call was made through
+/// a delegation's cap grant.
+pub fn authorize_proof(tree_id: &ActionHash, label: &str) -> ExternResult<Option<AgentPubKey>> {
+    let call_info = call_info()?;
+    let grant = match call_info.cap_grant {
+        CapGrant::ChainAuthor(_) => return Ok( None ),
+        CapGrant::RemoteAgent(grant) => grant,
+    };
+    let delegate = call_info.provenance;
+    let record = query_pending_records( EntryTypesUnit::Delegation )?
+        .into_iter()
+        .find(|record| DelegationEntry::grant_tag( record.action_address() ) == grant.tag )
+        .ok_or(guest_error!(format!("Cap grant '{}' is not for a proof delegation", grant.tag )))?;
+    let delegation = DelegationEntry::try_from_record( &record )?;
+
+    if !delegation.allows( &delegate, tree_id, label, &sys_time()? ) {
+        return Err(guest_error!(format!("Proof of '{}' in tree {} is outside the scope delegated to {}", label, tree_id, delegate )));
+    }
+
+    Ok( Some( delegate ) )
+}
+
+
+
+//
+// Author
+//
+/// Allow `delegate` to get proofs for some labels of a tree by calling this agent's cell
+///
+/// The returned `cap_secret` must be given to the delegate.
+#[hdk_extern]
+pub fn grant_proof_delegation(input: GrantProofDelegationInput) -> ExternResult<ProofDelegationPayload> {
+    debug!("Delegating proofs of {:?} in tree {} to: {}", input.labels, input.tree_id, input.delegate );
+    let entry = DelegationEntry {
+        delegate: input.delegate,
+        tree: input.tree_id,
+        labels: input.labels,
+        expires_at: input.expires_at,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let delegation_id = create_entry( entry.to_input() )?;
+    let cap_secret = generate_cap_secret()?;
+    let zome_name = zome_info()?.name;
+    let functions = DELEGATED_FUNCTIONS.iter()
+        .map(|name| ( zome_name.clone(), FunctionName::from( *name ) ) )
+        .collect();
+    let assignees : BTreeSet<AgentPubKey> = vec![ entry.delegate ].into_iter().collect();
+
+    create_cap_grant( CapGrantEntry {
+        tag: DelegationEntry::grant_tag( &delegation_id ),
+        access: CapAccess::Assigned {
+            secret: cap_secret,
+            assignees,
+        },
+        functions: GrantedFunctions::Listed( functions ),
+    })?;
+
+    Ok(
+        ProofDelegationPayload {
+            delegation_id,
+            cap_secret,
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn get_proof_delegations(_: ()) -> ExternResult<Vec<ProofDelegation>> {
+    query_pending_records( EntryTypesUnit::Delegation )?
+        .into_iter()
+        .map(|record| Ok(
+            ProofDelegation {
+                delegation_id: record.action_address().to_owned(),
+                delegation: DelegationEntry::try_from_record( &record )?,
+            }
+        ))
+        .collect()
+}
+
+
+/// Delete a delegation and its cap grant
+#[hdk_extern]
+pub fn revoke_proof_delegation(delegation_id: ActionHash) -> ExternResult<ActionHash> {
+    debug!("Revoking proof delegation: {}", delegation_id );
+    let tag = DelegationEntry::grant_tag( &delegation_id );
+    let grant = query(
+        ChainQueryFilter::new()
+            .entry_type( EntryType::CapGrant )
+            .include_entries( true )
+    )?.into_iter()
+        .find(|record| match record.entry().as_option() {
+            Some(Entry::CapGrant(grant)) => grant.tag == tag,
+            _ => false,
+        })
+        .ok_or(guest_error!(format!("No cap grant found for delegation {}", delegation_id )))?;
+
+    delete_cap_grant( grant.action_address().to_owned() )?;
+    delete_entry( delegation_id )
+}
+
+
+
+//
+// Delegate
+//
+fn delegated_proof_input(input: &DelegatedProofInput) -> GetLeafProofInput {
+    GetLeafProofInput {
+        tree_id: input.tree_id.clone(),
+        label: input.label.clone(),
+        recipient: None,
+    }
+}
+
+
+/// Get a leaf proof from another agent's tree using a delegation they granted
+#[hdk_extern]
+pub fn get_delegated_leaf_proof(input: DelegatedProofInput) -> ExternResult<LeafProofPayload> {
+    debug!("Requesting delegated proof for '{}' from: {}", input.label, input.author );
+    call_remote_agent_with_secret(
+        &input.author,
+        "get_leaf_proof",
+        Some( input.cap_secret ),
+        delegated_proof_input( &input ),
+    )
+}
+
+
+/// Get a sparse proof from another agent's tree using a delegation they granted
+#[hdk_extern]
+pub fn get_delegated_sparse_proof(input: DelegatedProofInput) -> ExternResult<SparseLeafProofPayload> {
+    debug!("Requesting delegated sparse proof for '{}' from: {}", input.label, input.author );
+    call_remote_agent_with_secret(
+        &input.author,
+        "get_sparse_proof",
+        Some( input.cap_secret ),
+        delegated_proof_input( &input ),
+    )
+}
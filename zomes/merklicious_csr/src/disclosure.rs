@@ -0,0 +1,61 @@
+use std::convert::TryInto;
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    ScopedTypeConnector,
+};
+use merklicious::{
+    EntryTypesUnit,
+    merklicious_sdk::{
+        // Entry Structs
+        DisclosureEntry,
+        // Input Structs
+        GetDisclosureHistoryInput,
+    },
+};
+
+
+/// Record that `labels` from `tree_id` were revealed to `recipient`
+pub fn record_disclosure(
+    recipient: &AgentPubKey,
+    tree_id: &ActionHash,
+    labels: Vec<String>,
+) -> ExternResult<ActionHash> {
+    debug!("Recording disclosure of {:?} from tree {} to: {}", labels, tree_id, recipient );
+    let entry = DisclosureEntry {
+        recipient: recipient.to_owned(),
+        labels,
+        tree: tree_id.to_owned(),
+        disclosed_at: sys_time()?,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )
+}
+
+
+/// List what this agent has revealed to others, oldest first
+#[hdk_extern]
+pub fn get_disclosure_history(input: GetDisclosureHistoryInput) -> ExternResult<Vec<DisclosureEntry>> {
+    Ok(
+        query(
+            ChainQueryFilter::new()
+                .entry_type( EntryTypesUnit::Disclosure.try_into()? )
+                .include_entries( true )
+        )?.iter()
+            .map( DisclosureEntry::try_from_record )
+            .collect::<ExternResult<Vec<DisclosureEntry>>>()?
+            .into_iter()
+            .filter(|disclosure| match &input.tree_id {
+                Some(tree_id) => disclosure.tree == *tree_id,
+                None => true,
+            })
+            .filter(|disclosure| match &input.recipient {
+                Some(recipient) => disclosure.recipient == *recipient,
+                None => true,
+            })
+            .collect()
+    )
+}
@@ -0,0 +1,48 @@
+use crate::hdk::prelude::*;
+use merklicious::{
+    merklicious_sdk::{
+        StandardMerkleTreeDump,
+        EthereumLeafProof,
+        // Input Structs
+        GetLeafProofInput,
+    },
+};
+use crate::{
+    delegation,
+    disclosure,
+    get_tree,
+    tree_data_blocks,
+};
+
+
+/// Export a tree's data blocks as an OpenZeppelin `StandardMerkleTree` dump
+#[hdk_extern]
+pub fn export_ethereum_tree(tree_id: ActionHash) -> ExternResult<StandardMerkleTreeDump> {
+    debug!("Exporting Ethereum tree: {}", tree_id );
+    let tree_entry = get_tree( tree_id.clone() )?;
+
+    StandardMerkleTreeDump::from_data_blocks( &tree_data_blocks( &tree_id, &tree_entry )? )
+}
+
+
+/// Get a proof that can be checked on-chain with OpenZeppelin's `MerkleProof.verify`
+#[hdk_extern]
+pub fn get_ethereum_leaf_proof(input: GetLeafProofInput) -> ExternResult<EthereumLeafProof> {
+    debug!("Get Ethereum proof for '{}' in tree: {}", input.label, input.tree_id );
+    let recipient = delegation::authorize_proof( &input.tree_id, &input.label )?
+        .or( input.recipient.clone() );
+    let tree_entry = get_tree( input.tree_id.clone() )?;
+    let leaf_proof = EthereumLeafProof::from_data_blocks( &tree_data_blocks( &input.tree_id, &tree_entry )?, &input.label )?;
+
+    if let Some(recipient) = &recipient {
+        disclosure::record_disclosure( recipient, &input.tree_id, vec![ input.label.clone() ] )?;
+    }
+
+    Ok( leaf_proof )
+}
+
+
+#[hdk_extern]
+pub fn verify_ethereum_leaf_proof(input: EthereumLeafProof) -> ExternResult<bool> {
+    input.verify()
+}
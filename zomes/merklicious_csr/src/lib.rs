@@ -4,10 +4,28 @@ pub use merklicious::hdi_extensions;
 pub use merklicious::hdk_extensions;
 pub use merklicious::holo_hash;
 
-use std::collections::BTreeMap;
+mod aggregate;
+mod anchor;
+mod attestation;
+mod backup;
+mod bundle;
+mod chunks;
+mod credential;
+mod delegation;
+mod disclosure;
+mod ethereum;
+mod proof_request;
+mod sd_jwt;
+mod log;
+mod mmr;
+mod sparse_tree;
+mod transport;
+
+use std::convert::TryInto;
+use std::collections::{ BTreeMap, HashSet };
 use lazy_static::lazy_static;
 use rand::Rng;
-use rs_merkle::{ MerkleTree, MerkleProof, algorithms };
+use rs_merkle::{ MerkleTree, algorithms };
 use hdk::prelude::*;
 use hdk_extensions::{
     must_get,
@@ -18,14 +36,22 @@ use hdi_extensions::{
 };
 use merklicious::{
     // EntryTypes,
-    // EntryTypesUnit,
+    EntryTypesUnit,
     // LinkTypes,
     merklicious_sdk::{
+        verify::VerifyError,
+        padding_leaf,
+        merkle_layers,
+        DEFAULT_CHUNK_SIZE,
+        HashAlgorithm,
+        MerkleHash,
         // Entry Structs
         LeafDataBlock,
         LeafProofPayload,
         DataBlocksEntry,
         TreeEntry,
+        TreeLayersEntry,
+        TreeType,
         // Input Structs
         CreateTreeInput,
         GetLeafProofInput,
@@ -43,34 +69,130 @@ lazy_static! {
     };
 }
 
+/// Functions that other agents are allowed to call with `call_remote`
+const REMOTE_FUNCTIONS: [&str; 4] = [
+    "handle_credential_request",
+    "receive_credential",
+    "handle_proof_request",
+    "recv_remote_signal",
+];
+
 
 #[hdk_extern]
 fn init(_: ()) -> ExternResult<InitCallbackResult> {
     debug!("'{}' init", *ZOME_NAME );
+    let zome_name = zome_info()?.name;
+    let functions = REMOTE_FUNCTIONS.iter()
+        .map(|name| ( zome_name.clone(), FunctionName::from( *name ) ) )
+        .collect();
+
+    create_cap_grant( CapGrantEntry {
+        tag: String::from("remote_calls"),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed( functions ),
+    })?;
+
     Ok(InitCallbackResult::Pass)
 }
 
 
 #[hdk_extern]
 fn whoami(_: ()) -> ExternResult<AgentInfo> {
-    Ok( agent_info()? )
+    agent_info()
+}
+
+
+/// Call a function in this zome on another agent's cell and decode the response
+fn call_remote_agent<I, O>(agent: &AgentPubKey, fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    call_remote_agent_with_secret( agent, fn_name, None, payload )
+}
+
+
+/// Same as [`call_remote_agent`] but for functions that need a cap secret (eg. delegations)
+fn call_remote_agent_with_secret<I, O>(
+    agent: &AgentPubKey,
+    fn_name: &str,
+    cap_secret: Option<CapSecret>,
+    payload: I,
+) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    let response = call_remote(
+        agent.to_owned(),
+        zome_info()?.name,
+        fn_name.into(),
+        cap_secret,
+        payload,
+    )?;
+
+    match response {
+        ZomeCallResponse::Ok(result) => Ok( result.decode()
+            .map_err(|err| guest_error!(format!("Failed to decode '{}' response: {:?}", fn_name, err )))? ),
+        other => Err(guest_error!(format!("Remote call '{}' to {} failed: {:?}", fn_name, agent, other ))),
+    }
+}
+
+
+/// Get the records of an entry type on this agent's source chain that have not been deleted
+///
+/// Used for request queues where an entry is deleted once the request is answered.
+fn query_pending_records(entry_type: EntryTypesUnit) -> ExternResult<Vec<Record>> {
+    let deleted : HashSet<ActionHash> = query(
+        ChainQueryFilter::new()
+            .action_type( ActionType::Delete )
+    )?.into_iter()
+        .filter_map(|record| match record.action() {
+            Action::Delete(delete) => Some( delete.deletes_address.clone() ),
+            _ => None,
+        })
+        .collect();
+
+    Ok(
+        query(
+            ChainQueryFilter::new()
+                .entry_type( entry_type.try_into()? )
+                .include_entries( true )
+        )?.into_iter()
+            .filter(|record| !deleted.contains( record.action_address() ) )
+            .collect()
+    )
+}
+
+
+/// Get the link base used for links about a root (eg. anchors and attestations)
+fn root_hash(root: &[u8; 32]) -> ExternalHash {
+    ExternalHash::from_raw_32( root.to_vec() )
 }
 
 
-fn create_merkle_tree(data_blocks: &Vec<LeafDataBlock>) -> ExternResult<(MerkleTree<algorithms::Sha256>, Vec<[u8; 32]>)> {
-    let leaves = data_blocks.clone().iter()
-        .map(|leaf| leaf.hash() )
-        .collect::<ExternResult<Vec<[u8; 32]>>>()?;
-    let tree = MerkleTree::<algorithms::Sha256>::from_leaves(&leaves);
+fn hash_data_blocks(data_blocks: &[LeafDataBlock]) -> ExternResult<Vec<[u8; 32]>> {
+    Ok(
+        data_blocks.iter()
+            .map(|leaf| leaf.hash() )
+            .collect::<Result<Vec<[u8; 32]>, VerifyError>>()?
+    )
+}
+
+
+fn create_merkle_tree(leaves: &[[u8; 32]]) -> MerkleTree<algorithms::Sha256> {
+    let tree = MerkleTree::<algorithms::Sha256>::from_leaves( leaves );
 
     debug!("Tree root: {:?}", tree.root() );
-    Ok( (tree, leaves) )
+    tree
 }
 
 
 #[hdk_extern]
 pub fn create_tree(input: CreateTreeInput) -> ExternResult<ActionHash> {
     debug!("Creating new tree entry: {:#?}", input );
+    let tree_type = input.tree_type.unwrap_or_default();
+    let hash_algorithm = input.hash_algorithm.unwrap_or_default();
     let entropy = match input.entropy {
         Some(bytes) => bytes.to_vec(),
         None => {
@@ -83,26 +205,93 @@ pub fn create_tree(input: CreateTreeInput) -> ExternResult<ActionHash> {
         .map(|(index, leaf_input)| {
             leaf_input.into_data_block( &entropy, index )
         })
-        .collect::<ExternResult<Vec<LeafDataBlock>>>()?;
-    let (merkle_tree, leaves) = create_merkle_tree( &data_blocks )?;
+        .collect::<Result<Vec<LeafDataBlock>, VerifyError>>()?;
+    let mut leaves = data_blocks.iter()
+        .map(|block| hash_algorithm.leaf_hash( block ) )
+        .collect::<Result<Vec<[u8; 32]>, VerifyError>>()?;
+
+    if !hash_algorithm.is_sha256() && tree_type != TreeType::Standard {
+        return Err(guest_error!(format!("{:?} trees only support {:?}; not {:?}", tree_type, HashAlgorithm::Sha256, hash_algorithm )));
+    }
+
+    if let Some(padding) = input.padding {
+        if tree_type != TreeType::Standard || !hash_algorithm.is_sha256() {
+            return Err(guest_error!(format!("Padding is only supported for standard {:?} trees", HashAlgorithm::Sha256 )));
+        }
 
-    let blocks_entry = DataBlocksEntry {
-        blocks: data_blocks,
+        let padded_size = padding.padded_size( leaves.len() as u64 )? as usize;
+        for index in leaves.len()..padded_size {
+            leaves.push( padding_leaf( &entropy, index )? );
+        }
+    }
 
-        // common fields
-        metadata: BTreeMap::new(),
+    let root = match tree_type {
+        TreeType::Standard => hash_algorithm.root( &leaves )
+            .ok_or(guest_error!(format!("Couldn't get the Merkle root")))?,
+        TreeType::Sparse => sparse_tree::create_sparse_merkle_tree( &data_blocks, &leaves )?.root(),
+    };
+
+    let chunk_size = input.chunk_size.unwrap_or( DEFAULT_CHUNK_SIZE );
+
+    if !chunk_size.is_power_of_two() {
+        return Err(guest_error!(format!("Chunk size must be a power of two; not {}", chunk_size )));
+    }
+
+    let chunked = leaves.len() as u64 > chunk_size;
+
+    if chunked && ( tree_type != TreeType::Standard || !hash_algorithm.is_sha256() ) {
+        return Err(guest_error!(format!("{:?} {:?} trees cannot have more than {} leaves", hash_algorithm, tree_type, chunk_size )));
+    }
+
+    let (data_blocks_id, chunks) = match chunked {
+        true => (None, chunks::create_tree_chunks( data_blocks, &leaves, chunk_size as usize )?),
+        false => {
+            let blocks_entry = DataBlocksEntry {
+                blocks: data_blocks,
+
+                // common fields
+                metadata: BTreeMap::new(),
+            };
+            ( Some( create_entry( blocks_entry.to_input() )? ), Vec::new() )
+        },
+    };
+
+    let layers = match input.store_layers.unwrap_or(false) {
+        true => {
+            if tree_type != TreeType::Standard || !hash_algorithm.is_sha256() {
+                return Err(guest_error!(format!("Stored layers are only supported for standard {:?} trees", HashAlgorithm::Sha256 )));
+            }
+            if chunked {
+                return Err(guest_error!(format!("Stored layers are not supported for chunked trees")));
+            }
+
+            let layers_entry = TreeLayersEntry {
+                layers: merkle_layers::build_layers( &leaves ),
+
+                // common fields
+                metadata: BTreeMap::new(),
+            };
+            Some( create_entry( layers_entry.to_input() )? )
+        },
+        false => None,
     };
-    let blocks_action_hash = create_entry( blocks_entry.to_input() )?;
 
     let entry = TreeEntry {
-        data_blocks: blocks_action_hash,
-        leaves,
+        data_blocks: data_blocks_id,
+        leaves: match chunked {
+            true => Vec::new(),
+            false => MerkleHash::from_raw_vec( leaves ),
+        },
         entropy: entropy.to_vec(),
-        root: merkle_tree.root()
-            .ok_or(guest_error!(format!("Couldn't get the Merkle root")))?,
+        root: root.into(),
+        tree_type,
+        layers,
+        chunks,
+        chunk_size: Some( chunk_size ),
+        hash_algorithm,
 
         // common fields
-        metadata: BTreeMap::new(),
+        metadata: input.metadata.unwrap_or_default(),
     };
     let action_hash = create_entry( entry.to_input() )?;
 
@@ -115,7 +304,47 @@ pub fn get_tree(tree_id: ActionHash) -> ExternResult<TreeEntry> {
     debug!("Get latest tree entry: {}", tree_id );
     let record = must_get( &tree_id )?;
 
-    Ok( TreeEntry::try_from_record( &record )? )
+    TreeEntry::try_from_record( &record )
+}
+
+
+fn tree_data_blocks_id(tree_id: &ActionHash, tree_entry: &TreeEntry) -> ExternResult<ActionHash> {
+    tree_entry.data_blocks.clone()
+        .ok_or(guest_error!(format!("Tree {} has no single data blocks entry", tree_id )))
+}
+
+
+/// Get every data block of a tree in leaf order
+fn tree_data_blocks(tree_id: &ActionHash, tree_entry: &TreeEntry) -> ExternResult<Vec<LeafDataBlock>> {
+    if !tree_entry.is_chunked() {
+        return Ok( get_data_blocks( tree_data_blocks_id( tree_id, tree_entry )? )?.blocks );
+    }
+
+    let mut data_blocks = Vec::new();
+
+    for chunk in tree_entry.chunks.iter() {
+        if let Some(data_blocks_id) = &chunk.data_blocks {
+            data_blocks.extend( get_data_blocks( data_blocks_id.to_owned() )?.blocks );
+        }
+    }
+
+    Ok( data_blocks )
+}
+
+
+/// Get every leaf hash of a tree, including padding
+fn tree_leaves(tree_entry: &TreeEntry) -> ExternResult<Vec<[u8; 32]>> {
+    if !tree_entry.is_chunked() {
+        return Ok( MerkleHash::to_raw_vec( &tree_entry.leaves ) );
+    }
+
+    let mut leaves = Vec::new();
+
+    for chunk in tree_entry.chunks.iter() {
+        leaves.extend( chunks::get_leaf_chunk( chunk.leaves.clone() )?.leaves );
+    }
+
+    Ok( leaves )
 }
 
 
@@ -124,38 +353,84 @@ pub fn get_data_blocks(data_blocks_id: ActionHash) -> ExternResult<DataBlocksEnt
     debug!("Get latest tree entry: {}", data_blocks_id );
     let record = must_get( &data_blocks_id )?;
 
-    Ok( DataBlocksEntry::try_from_record( &record )? )
+    DataBlocksEntry::try_from_record( &record )
+}
+
+
+#[hdk_extern]
+pub fn get_tree_layers(layers_id: ActionHash) -> ExternResult<TreeLayersEntry> {
+    debug!("Get tree layers entry: {}", layers_id );
+    let record = must_get( &layers_id )?;
+
+    TreeLayersEntry::try_from_record( &record )
 }
 
 
 #[hdk_extern]
 pub fn hash_data_block(input: LeafDataBlock) -> ExternResult<[u8; 32]> {
-    input.hash()
+    Ok( input.hash()? )
 }
 
 
 #[hdk_extern]
 pub fn get_leaf_proof(input: GetLeafProofInput) -> ExternResult<LeafProofPayload> {
+    // A delegate's proofs are always recorded as disclosed to the delegate
+    let recipient = delegation::authorize_proof( &input.tree_id, &input.label )?
+        .or( input.recipient.clone() );
+    let leaf_proof = create_leaf_proof( &input )?;
+
+    if let Some(recipient) = &recipient {
+        disclosure::record_disclosure( recipient, &input.tree_id, vec![ input.label.clone() ] )?;
+    }
+
+    Ok( leaf_proof )
+}
+
+
+fn create_leaf_proof(input: &GetLeafProofInput) -> ExternResult<LeafProofPayload> {
     debug!("Get proof for '{}' in tree: {}", input.label, input.tree_id );
     let tree_entry = get_tree( input.tree_id.clone() )?;
-    let data_blocks = get_data_blocks( tree_entry.data_blocks.clone() )?.blocks;
-    let (tree, _) = create_merkle_tree( &data_blocks )?;
+
+    if tree_entry.tree_type != TreeType::Standard {
+        return Err(guest_error!(format!("Tree {} is a {:?} tree; use the matching proof method", input.tree_id, tree_entry.tree_type )));
+    }
+
+    if tree_entry.is_chunked() {
+        return chunks::get_chunked_leaf_proof( input, &tree_entry );
+    }
+
+    let data_blocks = get_data_blocks( tree_data_blocks_id( &input.tree_id, &tree_entry )? )?.blocks;
+    // Padding leaves have no data block so they can never be targeted
     let target_index = data_blocks.iter()
         .position(|block| block.label == input.label )
         .ok_or(guest_error!(format!("Tree has no data block with the label '{}'", input.label )))?;
     let target = data_blocks[ target_index ].clone();
-    let leaf = target.hash()?;
-    let merkle_proof = tree.proof( &[target_index] );
+    let leaf = tree_entry.hash_algorithm.leaf_hash( &target )?;
+    let leaves = MerkleHash::to_raw_vec( &tree_entry.leaves );
+
+    if leaves.get( target_index ) != Some( &leaf ) {
+        return Err(guest_error!(format!("Data block '{}' does not match leaf {} of tree {}", input.label, target_index, input.tree_id )));
+    }
+
+    let proof = match tree_entry.layers {
+        Some(layers_id) => {
+            let layers = get_tree_layers( layers_id )?.layers;
+            merkle_layers::proof_from_layers( &leaves, &layers, target_index )
+                .ok_or(guest_error!(format!("Leaf {} is not in the stored layers of tree {}", target_index, input.tree_id )))?
+        },
+        None => tree_entry.hash_algorithm.proof( &leaves, target_index )
+            .ok_or(guest_error!(format!("Couldn't create proof for leaf {} of tree {}", target_index, input.tree_id )))?,
+    };
 
-    debug!("Tree root: {:?}", tree.root() );
     Ok(
         LeafProofPayload {
-            proof: merkle_proof.proof_hashes().to_vec(),
+            proof: MerkleHash::from_raw_vec( proof ),
             index: target_index as u64,
             target,
-            leaf,
+            leaf: leaf.into(),
             root: tree_entry.root,
-            total_leaves: data_blocks.len() as u64,
+            total_leaves: leaves.len() as u64,
+            hash_algorithm: tree_entry.hash_algorithm,
         }
     )
 }
@@ -163,6 +438,13 @@ pub fn get_leaf_proof(input: GetLeafProofInput) -> ExternResult<LeafProofPayload
 
 #[hdk_extern]
 pub fn verify_leaf_proof(input: VerifyLeafProofInput) -> ExternResult<bool> {
-    let proof = MerkleProof::<algorithms::Sha256>::new( input.proof );
-    Ok( proof.verify( input.root, &[ input.index as usize ], &[ input.leaf ], input.total_leaves as usize ) )
+    Ok(
+        input.hash_algorithm.verify_proof(
+            &input.root.0,
+            input.index,
+            &input.leaf.0,
+            &MerkleHash::to_raw_vec( &input.proof ),
+            input.total_leaves,
+        )
+    )
 }
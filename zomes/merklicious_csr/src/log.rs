@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use rand::Rng;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    LinkTypes,
+    merklicious_sdk::{
+        verify::VerifyError,
+        merkle_log,
+        // Entry Structs
+        LeafDataBlock,
+        LogEntry,
+        LogAppendEntry,
+        LogStatePayload,
+        ConsistencyProofPayload,
+        LogLeafProofPayload,
+        // Input Structs
+        CreateLogInput,
+        AppendLeavesInput,
+        GetConsistencyProofInput,
+        GetLogLeafProofInput,
+    },
+};
+
+
+#[hdk_extern]
+pub fn create_log(input: CreateLogInput) -> ExternResult<ActionHash> {
+    debug!("Creating new log entry: {:#?}", input );
+    let entropy = match input.entropy {
+        Some(bytes) => bytes.to_vec(),
+        None => {
+            let mut rng = rand::thread_rng();
+            (0..32).map(|_| rng.gen()).collect()
+        },
+    };
+    let entry = LogEntry {
+        entropy,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )
+}
+
+
+#[hdk_extern]
+pub fn get_log(log_id: ActionHash) -> ExternResult<LogEntry> {
+    debug!("Get log entry: {}", log_id );
+    let record = must_get( &log_id )?;
+
+    LogEntry::try_from_record( &record )
+}
+
+
+/// Get every append of a log in order, checking that they form a contiguous sequence
+fn get_log_appends(log_id: &ActionHash) -> ExternResult<Vec<LogAppendEntry>> {
+    let mut appends = get_links( log_id.clone(), LinkTypes::LogAppend, None )?
+        .into_iter()
+        .map(|link| {
+            let append_id = link.target.into_action_hash()
+                .ok_or(guest_error!(format!("LogAppend link target is not an action hash")))?;
+            let record = must_get( &append_id )?;
+
+            LogAppendEntry::try_from_record( &record )
+        })
+        .collect::<ExternResult<Vec<LogAppendEntry>>>()?;
+
+    appends.sort_by_key(|append| append.start );
+
+    let mut size = 0;
+    for append in appends.iter() {
+        if append.start != size {
+            return Err(guest_error!(format!("Log {} has conflicting appends at index {}", log_id, append.start )));
+        }
+        size = append.size;
+    }
+
+    Ok( appends )
+}
+
+
+/// Get all leaf hashes of a log in order
+fn get_log_leaves(log_id: &ActionHash) -> ExternResult<Vec<[u8; 32]>> {
+    Ok(
+        get_log_appends( log_id )?.into_iter()
+            .flat_map(|append| append.leaves )
+            .collect()
+    )
+}
+
+
+#[hdk_extern]
+pub fn get_log_state(log_id: ActionHash) -> ExternResult<LogStatePayload> {
+    let leaves = get_log_leaves( &log_id )?;
+
+    Ok(
+        LogStatePayload {
+            size: leaves.len() as u64,
+            root: merkle_log::root( &leaves ),
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn append_leaves(input: AppendLeavesInput) -> ExternResult<ActionHash> {
+    debug!("Appending {} leaves to log: {}", input.leaves.len(), input.log_id );
+    if input.leaves.is_empty() {
+        return Err(guest_error!(format!("Cannot append an empty batch of leaves to log {}", input.log_id )));
+    }
+
+    let record = must_get( &input.log_id )?;
+
+    if record.action().author() != &agent_info()?.agent_initial_pubkey {
+        return Err(guest_error!(format!("Only the author of log {} can append to it", input.log_id )));
+    }
+
+    let log = LogEntry::try_from_record( &record )?;
+    let mut leaves = get_log_leaves( &input.log_id )?;
+    let start = leaves.len();
+    let data_blocks = input.leaves.into_iter()
+        .enumerate()
+        .map(|(index, leaf_input)| {
+            leaf_input.into_data_block( &log.entropy, start + index )
+        })
+        .collect::<Result<Vec<LeafDataBlock>, VerifyError>>()?;
+    let new_leaves = data_blocks.iter()
+        .map(|block| Ok( merkle_log::leaf_hash( &block.hash()? ) ) )
+        .collect::<ExternResult<Vec<[u8; 32]>>>()?;
+
+    leaves.extend( new_leaves.iter().cloned() );
+
+    let entry = LogAppendEntry {
+        log: input.log_id.clone(),
+        start: start as u64,
+        data_blocks,
+        leaves: new_leaves,
+        size: leaves.len() as u64,
+        root: merkle_log::root( &leaves ),
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let action_hash = create_entry( entry.to_input() )?;
+
+    create_link(
+        input.log_id,
+        action_hash.clone(),
+        LinkTypes::LogAppend,
+        (start as u64).to_be_bytes().to_vec(),
+    )?;
+
+    Ok( action_hash )
+}
+
+
+#[hdk_extern]
+pub fn get_consistency_proof(input: GetConsistencyProofInput) -> ExternResult<ConsistencyProofPayload> {
+    debug!("Get consistency proof for log {} from size {} to {}", input.log_id, input.old_size, input.new_size );
+    let leaves = get_log_leaves( &input.log_id )?;
+
+    if input.old_size > input.new_size || input.new_size > leaves.len() as u64 {
+        return Err(guest_error!(format!("Invalid consistency range {} -> {} for log of size {}", input.old_size, input.new_size, leaves.len() )));
+    }
+
+    let new_leaves = &leaves[ ..input.new_size as usize ];
+    let proof = match input.old_size {
+        0 => Vec::new(),
+        old_size => merkle_log::consistency_proof( new_leaves, old_size as usize )
+            .ok_or(guest_error!(format!("Couldn't create consistency proof")))?,
+    };
+
+    Ok(
+        ConsistencyProofPayload {
+            old_size: input.old_size,
+            new_size: input.new_size,
+            old_root: merkle_log::root( &leaves[ ..input.old_size as usize ] ),
+            new_root: merkle_log::root( new_leaves ),
+            proof,
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn verify_consistency_proof(input: ConsistencyProofPayload) -> ExternResult<bool> {
+    Ok( input.verify() )
+}
+
+
+#[hdk_extern]
+pub fn get_log_leaf_proof(input: GetLogLeafProofInput) -> ExternResult<LogLeafProofPayload> {
+    debug!("Get proof for leaf {} in log: {}", input.index, input.log_id );
+    let appends = get_log_appends( &input.log_id )?;
+    let leaves : Vec<[u8; 32]> = appends.iter()
+        .flat_map(|append| append.leaves.iter().cloned() )
+        .collect();
+    let size = input.size.unwrap_or( leaves.len() as u64 );
+
+    if input.index >= size || size > leaves.len() as u64 {
+        return Err(guest_error!(format!("Leaf {} is not in log {} of size {}", input.index, input.log_id, size )));
+    }
+
+    let target = appends.iter()
+        .find(|append| input.index < append.size )
+        .and_then(|append| append.data_blocks.get( (input.index - append.start) as usize ) )
+        .cloned()
+        .ok_or(guest_error!(format!("Log {} is missing the data block for leaf {}", input.log_id, input.index )))?;
+    let sized_leaves = &leaves[ ..size as usize ];
+    let proof = merkle_log::inclusion_proof( sized_leaves, input.index as usize )
+        .ok_or(guest_error!(format!("Couldn't create inclusion proof for leaf {}", input.index )))?;
+
+    Ok(
+        LogLeafProofPayload {
+            proof,
+            index: input.index,
+            target,
+            leaf: leaves[ input.index as usize ],
+            root: merkle_log::root( sized_leaves ),
+            size,
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn verify_log_leaf_proof(input: LogLeafProofPayload) -> ExternResult<bool> {
+    input.verify()
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use rand::Rng;
+use crate::hdk::prelude::*;
+use crate::hdk_extensions::{
+    must_get,
+};
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    LinkTypes,
+    merklicious_sdk::{
+        merkle_log,
+        mmr,
+        // Entry Structs
+        MmrEntry,
+        MmrAppendEntry,
+        MmrStatePayload,
+        MmrProofPayload,
+        // Input Structs
+        CreateMmrInput,
+        AppendMmrLeafInput,
+        GetMmrProofInput,
+    },
+};
+
+
+#[hdk_extern]
+pub fn create_mmr(input: CreateMmrInput) -> ExternResult<ActionHash> {
+    debug!("Creating new MMR entry: {:#?}", input );
+    let entropy = match input.entropy {
+        Some(bytes) => bytes.to_vec(),
+        None => {
+            let mut rng = rand::thread_rng();
+            (0..32).map(|_| rng.gen()).collect()
+        },
+    };
+    let entry = MmrEntry {
+        entropy,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )
+}
+
+
+#[hdk_extern]
+pub fn get_mmr(mmr_id: ActionHash) -> ExternResult<MmrEntry> {
+    debug!("Get MMR entry: {}", mmr_id );
+    let record = must_get( &mmr_id )?;
+
+    MmrEntry::try_from_record( &record )
+}
+
+
+fn index_tag(index: u64) -> LinkTag {
+    LinkTag::new( index.to_be_bytes().to_vec() )
+}
+
+
+fn get_append_from_link(link: Link) -> ExternResult<MmrAppendEntry> {
+    let append_id = link.target.into_action_hash()
+        .ok_or(guest_error!(format!("MmrAppend link target is not an action hash")))?;
+    let record = must_get( &append_id )?;
+
+    MmrAppendEntry::try_from_record( &record )
+}
+
+
+/// Get the append for the leaf at `index`
+///
+/// Only the link with the matching tag is fetched so the cost does not grow with the MMR.
+fn get_mmr_append(mmr_id: &ActionHash, index: u64) -> ExternResult<MmrAppendEntry> {
+    let link = get_links( mmr_id.clone(), LinkTypes::MmrAppend, Some( index_tag( index ) ) )?
+        .into_iter()
+        .next()
+        .ok_or(guest_error!(format!("MMR {} has no leaf at index {}", mmr_id, index )))?;
+
+    get_append_from_link( link )
+}
+
+
+fn has_mmr_append(mmr_id: &ActionHash, index: u64) -> ExternResult<bool> {
+    Ok( !get_links( mmr_id.clone(), LinkTypes::MmrAppend, Some( index_tag( index ) ) )?.is_empty() )
+}
+
+
+/// Get the number of leaves in an MMR
+///
+/// Appends are linked by index, so the size is found with a doubling then binary search over
+/// single-tag lookups instead of loading every append.
+fn get_mmr_size(mmr_id: &ActionHash) -> ExternResult<u64> {
+    if !has_mmr_append( mmr_id, 0 )? {
+        return Ok( 0 );
+    }
+
+    // Invariant: `lower` has an append and `upper` does not
+    let mut lower = 0;
+    let mut upper = 1;
+
+    while has_mmr_append( mmr_id, upper )? {
+        lower = upper;
+        upper *= 2;
+    }
+
+    while upper - lower > 1 {
+        let middle = lower + ( upper - lower ) / 2;
+
+        match has_mmr_append( mmr_id, middle )? {
+            true => lower = middle,
+            false => upper = middle,
+        }
+    }
+
+    Ok( upper )
+}
+
+
+/// Get the latest append of an MMR, or `None` if it is empty
+///
+/// Only the MMR's author can link appends to it (see the integrity zome's link validation), so the
+/// append at the highest index holds the current peaks.
+fn get_latest_append(mmr_id: &ActionHash) -> ExternResult<Option<MmrAppendEntry>> {
+    match get_mmr_size( mmr_id )? {
+        0 => Ok( None ),
+        size => Ok( Some( get_mmr_append( mmr_id, size - 1 )? ) ),
+    }
+}
+
+
+fn mmr_state(latest: &Option<MmrAppendEntry>) -> MmrStatePayload {
+    let (size, peaks) = match latest {
+        Some(append) => ( append.index + 1, append.peaks.clone() ),
+        None => ( 0, Vec::new() ),
+    };
+
+    MmrStatePayload {
+        size,
+        root: mmr::bag_peaks( &peaks ),
+        peaks,
+    }
+}
+
+
+#[hdk_extern]
+pub fn get_mmr_state(mmr_id: ActionHash) -> ExternResult<MmrStatePayload> {
+    Ok( mmr_state( &get_latest_append( &mmr_id )? ) )
+}
+
+
+#[hdk_extern]
+pub fn append_mmr_leaf(input: AppendMmrLeafInput) -> ExternResult<ActionHash> {
+    debug!("Appending leaf '{}' to MMR: {}", input.leaf.label, input.mmr_id );
+    let record = must_get( &input.mmr_id )?;
+
+    if record.action().author() != &agent_info()?.agent_initial_pubkey {
+        return Err(guest_error!(format!("Only the author of MMR {} can append to it", input.mmr_id )));
+    }
+
+    let mmr_entry = MmrEntry::try_from_record( &record )?;
+
+    let state = mmr_state( &get_latest_append( &input.mmr_id )? );
+    let data_block = input.leaf.into_data_block( &mmr_entry.entropy, state.size as usize )?;
+    let leaf = merkle_log::leaf_hash( &data_block.hash()? );
+    let (nodes, peaks) = mmr::append( &state.peaks, state.size, leaf );
+
+    let entry = MmrAppendEntry {
+        mmr: input.mmr_id.clone(),
+        index: state.size,
+        data_block,
+        nodes,
+        peaks,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let action_hash = create_entry( entry.to_input() )?;
+
+    create_link(
+        input.mmr_id,
+        action_hash.clone(),
+        LinkTypes::MmrAppend,
+        index_tag( state.size ),
+    )?;
+
+    Ok( action_hash )
+}
+
+
+#[hdk_extern]
+pub fn get_mmr_proof(input: GetMmrProofInput) -> ExternResult<MmrProofPayload> {
+    debug!("Get proof for leaf {} in MMR: {}", input.index, input.mmr_id );
+    let state = get_mmr_state( input.mmr_id.clone() )?;
+    let target_append = get_mmr_append( &input.mmr_id, input.index )?;
+    let siblings = mmr::proof_nodes( input.index, state.size )
+        .ok_or(guest_error!(format!("Leaf {} is not in MMR {} of size {}", input.index, input.mmr_id, state.size )))?
+        .into_iter()
+        .map(|(height, index)| {
+            // Each node is stored by the append of the last leaf it covers
+            let append = get_mmr_append( &input.mmr_id, mmr::created_by( height, index ) )?;
+
+            append.nodes.get( height as usize ).cloned()
+                .ok_or(guest_error!(format!("MMR append {} is missing the node at height {}", append.index, height )))
+        })
+        .collect::<ExternResult<Vec<[u8; 32]>>>()?;
+
+    Ok(
+        MmrProofPayload {
+            siblings,
+            peaks: state.peaks,
+            index: input.index,
+            size: state.size,
+            leaf: target_append.nodes[0],
+            target: target_append.data_block,
+            root: state.root,
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn verify_mmr_proof(input: MmrProofPayload) -> ExternResult<bool> {
+    input.verify()
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    guest_error,
+    ScopedTypeConnector,
+};
+use merklicious::{
+    EntryTypesUnit,
+    merklicious_sdk::{
+        w3c::VerifiablePresentation,
+        // Entry Structs
+        ProofRequestEntry,
+        SentProofRequestEntry,
+        PendingProofRequest,
+        PresentationPayload,
+        MerkliciousSignal,
+        PeerSignal,
+        // Input Structs
+        GetLeafProofInput,
+        RequestProofInput,
+        ApproveProofRequestInput,
+    },
+};
+use crate::{
+    call_remote_agent,
+    query_pending_records,
+    get_tree,
+    get_leaf_proof,
+    disclosure::{
+        record_disclosure,
+    },
+};
+
+
+//
+// Verifier
+//
+/// Ask `holder` for proofs of the given labels
+///
+/// The request is also recorded on this agent's source chain so that only answers to it are
+/// accepted.  Returns the ID of the request on the holder's source chain.  The answer arrives as a
+/// [`MerkliciousSignal::ProofReceived`] or [`MerkliciousSignal::ProofDenied`] signal.
+#[hdk_extern]
+pub fn request_proof(input: RequestProofInput) -> ExternResult<ActionHash> {
+    debug!("Requesting proof of {:?} from holder: {}", input.labels, input.holder );
+    let request_id : ActionHash = call_remote_agent( &input.holder.clone(), "handle_proof_request", input.clone() )?;
+    let entry = SentProofRequestEntry {
+        holder: input.holder,
+        request_id: request_id.clone(),
+        labels: input.labels,
+        root: input.root,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+
+    create_entry( entry.to_input() )?;
+
+    Ok( request_id )
+}
+
+
+/// Find the sent request that a holder's answer is for
+fn get_sent_proof_request(
+    holder: &AgentPubKey,
+    request_id: &ActionHash,
+) -> ExternResult<(ActionHash, SentProofRequestEntry)> {
+    for record in query_pending_records( EntryTypesUnit::SentProofRequest )? {
+        let request = SentProofRequestEntry::try_from_record( &record )?;
+
+        if request.holder == *holder && request.request_id == *request_id {
+            return Ok( (record.action_address().to_owned(), request) );
+        }
+    }
+
+    Err(guest_error!(format!("No pending proof request {} to holder {}", request_id, holder )))
+}
+
+
+/// Handle answers to this agent's proof requests
+///
+/// Answers are dropped unless they are for a pending request to the sender, and presentations are
+/// dropped unless they prove exactly the requested labels.
+#[hdk_extern]
+fn recv_remote_signal(signal: PeerSignal) -> ExternResult<()> {
+    let sender = call_info()?.provenance;
+    debug!("Received remote signal from {}: {:?}", sender, signal );
+
+    match signal {
+        PeerSignal::ProofResponse { presentation } => {
+            if presentation.holder != sender {
+                return Err(guest_error!(format!("Presentation holder ({}) does not match the sender ({})", presentation.holder, sender )));
+            }
+
+            let (sent_request_id, sent_request) = get_sent_proof_request( &sender, &presentation.request_id )?;
+            let labels : Vec<String> = presentation.proofs.iter()
+                .map(|proof| proof.target.label.clone() )
+                .collect();
+
+            if labels != sent_request.labels {
+                return Err(guest_error!(format!("Presentation labels {:?} do not match request {}", labels, presentation.request_id )));
+            }
+
+            if let Some(root) = sent_request.root {
+                if root != presentation.root {
+                    return Err(guest_error!(format!("Presentation is not for the requested root")));
+                }
+            }
+
+            if !presentation.verify()? {
+                return Err(guest_error!(format!("Presentation for request {} is not valid", presentation.request_id )));
+            }
+
+            delete_entry( sent_request_id )?;
+            emit_signal( MerkliciousSignal::ProofReceived { presentation } )
+        },
+        PeerSignal::ProofDenied { request_id } => {
+            let (sent_request_id, _) = get_sent_proof_request( &sender, &request_id )?;
+
+            delete_entry( sent_request_id )?;
+            emit_signal( MerkliciousSignal::ProofDenied {
+                request_id,
+                holder: sender,
+            })
+        },
+    }
+}
+
+
+#[hdk_extern]
+pub fn verify_presentation(input: PresentationPayload) -> ExternResult<bool> {
+    input.verify()
+}
+
+
+/// Convert a presentation into a W3C Verifiable Presentation for relying parties outside Holochain
+#[hdk_extern]
+pub fn export_verifiable_presentation(input: PresentationPayload) -> ExternResult<VerifiablePresentation> {
+    VerifiablePresentation::from_presentation( &input )
+}
+
+
+#[hdk_extern]
+pub fn verify_verifiable_presentation(input: VerifiablePresentation) -> ExternResult<bool> {
+    input.verify()
+}
+
+
+
+//
+// Holder
+//
+/// Queue a proof request from a remote verifier for approval
+#[hdk_extern]
+pub fn handle_proof_request(input: RequestProofInput) -> ExternResult<ActionHash> {
+    let verifier = call_info()?.provenance;
+    debug!("Received proof request from verifier: {}", verifier );
+    let entry = ProofRequestEntry {
+        verifier: verifier.clone(),
+        labels: input.labels,
+        root: input.root,
+
+        // common fields
+        metadata: BTreeMap::new(),
+    };
+    let request_id = create_entry( entry.to_input() )?;
+
+    emit_signal( MerkliciousSignal::ProofRequested {
+        request_id: request_id.clone(),
+        verifier,
+        labels: entry.labels,
+    })?;
+
+    Ok( request_id )
+}
+
+
+#[hdk_extern]
+pub fn get_proof_requests(_: ()) -> ExternResult<Vec<PendingProofRequest>> {
+    query_pending_records( EntryTypesUnit::ProofRequest )?
+        .into_iter()
+        .map(|record| Ok(
+            PendingProofRequest {
+                request_id: record.action_address().to_owned(),
+                request: ProofRequestEntry::try_from_record( &record )?,
+            }
+        ))
+        .collect()
+}
+
+
+/// Get a proof request that has not been approved or denied yet
+fn get_proof_request(request_id: &ActionHash) -> ExternResult<ProofRequestEntry> {
+    let record = query_pending_records( EntryTypesUnit::ProofRequest )?
+        .into_iter()
+        .find(|record| record.action_address() == request_id )
+        .ok_or(guest_error!(format!("Proof request {} is not pending", request_id )))?;
+
+    ProofRequestEntry::try_from_record( &record )
+}
+
+
+/// Answer a proof request with a presentation from the given tree
+#[hdk_extern]
+pub fn approve_proof_request(input: ApproveProofRequestInput) -> ExternResult<PresentationPayload> {
+    debug!("Approving proof request {} with tree: {}", input.request_id, input.tree_id );
+    let request = get_proof_request( &input.request_id )?;
+    let tree_entry = get_tree( input.tree_id.clone() )?;
+
+    if let Some(root) = request.root {
+        if root != tree_entry.root {
+            return Err(guest_error!(format!("Tree {} does not have the requested root", input.tree_id )));
+        }
+    }
+
+    let proofs = request.labels.iter()
+        .map(|label| get_leaf_proof( GetLeafProofInput {
+            tree_id: input.tree_id.clone(),
+            label: label.to_owned(),
+            recipient: None,
+        }))
+        .collect::<ExternResult<Vec<_>>>()?;
+    let presentation = PresentationPayload {
+        request_id: input.request_id.clone(),
+        holder: agent_info()?.agent_initial_pubkey,
+        root: tree_entry.root.0,
+        proofs,
+    };
+
+    record_disclosure( &request.verifier, &input.tree_id, request.labels.clone() )?;
+    remote_signal(
+        PeerSignal::ProofResponse {
+            presentation: presentation.clone(),
+        },
+        vec![ request.verifier ],
+    )?;
+    delete_entry( input.request_id )?;
+
+    Ok( presentation )
+}
+
+
+/// Refuse a proof request
+#[hdk_extern]
+pub fn deny_proof_request(request_id: ActionHash) -> ExternResult<ActionHash> {
+    debug!("Denying proof request: {}", request_id );
+    let request = get_proof_request( &request_id )?;
+
+    remote_signal(
+        PeerSignal::ProofDenied {
+            request_id: request_id.clone(),
+        },
+        vec![ request.verifier ],
+    )?;
+
+    delete_entry( request_id )
+}
@@ -0,0 +1,44 @@
+use crate::hdk::prelude::*;
+use merklicious::{
+    merklicious_sdk::{
+        sd_jwt::{
+            self,
+            SdJwt,
+            VerifiedSdJwt,
+        },
+        // Input Structs
+        PresentSdJwtInput,
+    },
+};
+use crate::{
+    get_tree,
+    tree_data_blocks,
+};
+
+
+/// Issue an SD-JWT with a disclosure for every data block of a tree
+#[hdk_extern]
+pub fn issue_sd_jwt(tree_id: ActionHash) -> ExternResult<SdJwt> {
+    debug!("Issuing SD-JWT for tree: {}", tree_id );
+    let tree_entry = get_tree( tree_id.clone() )?;
+
+    SdJwt::issue(
+        &agent_info()?.agent_initial_pubkey,
+        &tree_data_blocks( &tree_id, &tree_entry )?,
+        &tree_entry.root,
+        &sys_time()?,
+    )
+}
+
+
+/// Create an SD-JWT presentation that only reveals the given labels
+#[hdk_extern]
+pub fn present_sd_jwt(input: PresentSdJwtInput) -> ExternResult<String> {
+    input.sd_jwt.present( &input.labels )
+}
+
+
+#[hdk_extern]
+pub fn verify_sd_jwt(presentation: String) -> ExternResult<VerifiedSdJwt> {
+    sd_jwt::verify_presentation( &presentation )
+}
@@ -0,0 +1,87 @@
+use crate::hdk::prelude::*;
+use crate::hdi_extensions::{
+    guest_error,
+};
+use merklicious::{
+    merklicious_sdk::{
+        sparse_merkle_tree::label_key,
+        MerkleHash,
+        SparseMerkleTree,
+        // Entry Structs
+        LeafDataBlock,
+        SparseLeafProofPayload,
+        TreeType,
+        // Input Structs
+        GetLeafProofInput,
+        VerifySparseProofInput,
+    },
+};
+use crate::{
+    get_tree,
+    get_data_blocks,
+    tree_data_blocks_id,
+    delegation::{
+        authorize_proof,
+    },
+    disclosure::{
+        record_disclosure,
+    },
+};
+
+
+/// Build a sparse Merkle tree keyed by the label of each data block
+///
+/// `leaves` must be the hashes of `data_blocks` in the same order.
+pub fn create_sparse_merkle_tree(data_blocks: &[LeafDataBlock], leaves: &[[u8; 32]]) -> ExternResult<SparseMerkleTree> {
+    let mut tree = SparseMerkleTree::new();
+
+    for (block, leaf) in data_blocks.iter().zip( leaves.iter() ) {
+        if tree.insert( label_key( &block.label ), *leaf ).is_some() {
+            return Err(guest_error!(format!("Sparse trees cannot have duplicate labels; found '{}' more than once", block.label )));
+        }
+    }
+
+    debug!("Sparse tree root: {:?}", tree.root() );
+    Ok( tree )
+}
+
+
+#[hdk_extern]
+pub fn get_sparse_proof(input: GetLeafProofInput) -> ExternResult<SparseLeafProofPayload> {
+    debug!("Get sparse proof for '{}' in tree: {}", input.label, input.tree_id );
+    // A delegate's proofs are always recorded as disclosed to the delegate
+    let recipient = authorize_proof( &input.tree_id, &input.label )?
+        .or( input.recipient.clone() );
+    let tree_entry = get_tree( input.tree_id.clone() )?;
+
+    if tree_entry.tree_type != TreeType::Sparse {
+        return Err(guest_error!(format!("Tree {} is not a sparse tree", input.tree_id )));
+    }
+
+    let data_blocks = get_data_blocks( tree_data_blocks_id( &input.tree_id, &tree_entry )? )?.blocks;
+    let tree = create_sparse_merkle_tree( &data_blocks, &MerkleHash::to_raw_vec( &tree_entry.leaves ) )?;
+    let key = label_key( &input.label );
+    let target = data_blocks.into_iter()
+        .find(|block| block.label == input.label );
+    let leaf = tree.get( &key ).cloned();
+
+    if let Some(recipient) = &recipient {
+        record_disclosure( recipient, &input.tree_id, vec![ input.label.clone() ] )?;
+    }
+
+    Ok(
+        SparseLeafProofPayload {
+            proof: tree.proof( &key ),
+            label: input.label,
+            target,
+            leaf,
+            root: tree_entry.root.0,
+        }
+    )
+}
+
+
+#[hdk_extern]
+pub fn verify_sparse_proof(input: VerifySparseProofInput) -> ExternResult<bool> {
+    Ok( input.verify() )
+}
@@ -0,0 +1,53 @@
+use crate::hdk::prelude::*;
+use merklicious::{
+    merklicious_sdk::{
+        transport,
+        LeafProofPayload,
+        PresentationPayload,
+        // Input Structs
+        EncodeCompactProofInput,
+        DecodeCompactProofInput,
+    },
+};
+
+
+/// Encode a leaf proof as a versioned multibase string
+#[hdk_extern]
+pub fn encode_leaf_proof(input: LeafProofPayload) -> ExternResult<String> {
+    Ok( transport::encode( &input )? )
+}
+
+
+#[hdk_extern]
+pub fn decode_leaf_proof(input: String) -> ExternResult<LeafProofPayload> {
+    Ok( transport::decode( &input )? )
+}
+
+
+/// Encode a presentation as a versioned multibase string
+#[hdk_extern]
+pub fn encode_presentation(input: PresentationPayload) -> ExternResult<String> {
+    Ok( transport::encode( &input )? )
+}
+
+
+#[hdk_extern]
+pub fn decode_presentation(input: String) -> ExternResult<PresentationPayload> {
+    Ok( transport::decode( &input )? )
+}
+
+
+/// Encode a leaf proof as compact bytes for a QR code
+#[hdk_extern]
+pub fn encode_compact_leaf_proof(input: EncodeCompactProofInput) -> ExternResult<serde_bytes::ByteBuf> {
+    Ok( serde_bytes::ByteBuf::from( input.proof.to_compact( input.root_prefix, input.budget )? ) )
+}
+
+
+/// Rebuild the full leaf proof from compact bytes so that it can be verified
+///
+/// Proofs with a truncated root are rejected unless the full `expected_root` is given.
+#[hdk_extern]
+pub fn decode_compact_leaf_proof(input: DecodeCompactProofInput) -> ExternResult<LeafProofPayload> {
+    LeafProofPayload::from_compact( &input.bytes, input.expected_root.as_ref() )
+}